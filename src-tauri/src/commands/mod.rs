@@ -1,18 +1,30 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::Duration;
 use tauri::State;
 
 use crate::ai;
 use crate::config;
 use crate::git;
+use crate::jobs::{JobInfo, JobRegistry};
+use crate::locks::{self, OperationGuard, RepoLocks, Wait};
 use crate::setup;
+use crate::watcher::WatcherRegistry;
 
 pub struct AppState {
     pub repos: Mutex<HashMap<String, PathBuf>>,
+    pub watchers: WatcherRegistry,
+    pub jobs: JobRegistry,
+    pub locks: RepoLocks,
 }
 
+/// How long a write command waits in the repository's queue behind earlier operations
+/// (usually a background push or pull) before giving up with a "busy" error. Write
+/// commands are async so the wait happens off the main thread.
+const COMMAND_LOCK_WAIT: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepoSelectionResult {
     pub is_git: bool,
@@ -26,6 +38,11 @@ fn get_context_key(window_label: &str, tab_id: Option<&str>) -> String {
     }
 }
 
+/// Window that owns a context key ("<window label>" or "<window label>:<tab id>").
+pub(crate) fn window_label(context_key: &str) -> &str {
+    context_key.split(':').next().unwrap_or(context_key)
+}
+
 fn get_repo_path(state: &State<AppState>, context_key: &str) -> Result<PathBuf, String> {
     let repos = state.repos.lock().unwrap();
     repos
@@ -34,6 +51,26 @@ fn get_repo_path(state: &State<AppState>, context_key: &str) -> Result<PathBuf,
         .ok_or_else(|| "No repository selected for this context".to_string())
 }
 
+/// Resolves the context's repository and takes its operation lock for a write command.
+/// The lock is released when the returned guard is dropped.
+fn lock_repo(
+    state: &State<AppState>,
+    context_key: &str,
+    kind: &str,
+) -> Result<(PathBuf, OperationGuard), String> {
+    let repo_path = get_repo_path(state, context_key)?;
+    let guard = state
+        .locks
+        .acquire(
+            &repo_path,
+            kind,
+            Some(context_key),
+            Wait::Timeout(COMMAND_LOCK_WAIT),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok((repo_path, guard))
+}
+
 #[tauri::command]
 pub fn set_repository(
     path: String,
@@ -53,6 +90,9 @@ pub fn set_repository(
 
     let is_git = git::is_git_repo(&repo_path);
 
+    // A watcher belongs to the previously selected repository
+    state.watchers.unwatch(&context_key);
+
     if is_git {
         state.repos.lock().unwrap().insert(context_key, repo_path);
     } else {
@@ -68,12 +108,35 @@ pub fn set_repository(
 #[tauri::command]
 pub fn unset_repository(context_key: String, state: State<AppState>) -> Result<(), String> {
     state.repos.lock().unwrap().remove(&context_key);
+    state.watchers.unwatch(&context_key);
     Ok(())
 }
 
 #[tauri::command]
 pub fn unset_tab_repository(context_key: String, state: State<AppState>) -> Result<(), String> {
     state.repos.lock().unwrap().remove(&context_key);
+    state.watchers.unwatch(&context_key);
+    Ok(())
+}
+
+/// Starts pushing `repo-status-changed` events for this context instead of relying on polling.
+#[tauri::command]
+pub fn watch_repository_cmd(
+    context_key: String,
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    state
+        .watchers
+        .watch(app_handle, &context_key, &repo_path)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn unwatch_repository_cmd(context_key: String, state: State<AppState>) -> Result<(), String> {
+    state.watchers.unwatch(&context_key);
     Ok(())
 }
 
@@ -106,7 +169,10 @@ pub fn get_git_status(
 ) -> Result<git::RepoStatus, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::get_status(&repo_path).map_err(|e| e.to_string())
+    state
+        .watchers
+        .get_status(&context_key, &repo_path)
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -133,54 +199,173 @@ pub fn get_all_diff_cmd(
 }
 
 #[tauri::command]
-pub fn stage_file_cmd(
-    file_path: String,
+pub fn get_all_diff_files_cmd(
+    staged: bool,
     context_key: String,
     state: State<AppState>,
-) -> Result<(), String> {
+) -> Result<Vec<git::FileDiff>, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::stage_file(&repo_path, &file_path).map_err(|e| e.to_string())
+    git::get_all_diff_files(&repo_path, staged).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn stage_all_cmd(context_key: String, state: State<AppState>) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+pub async fn stage_file_cmd(
+    file_path: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stage")?;
+
+    git::stage_file(&repo_path, &file_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn stage_all_cmd(context_key: String, state: State<'_, AppState>) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stage")?;
+
+    git::stage_all(&repo_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn unstage_file_cmd(
+    file_path: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "unstage")?;
 
-    git::stage_all(&repo_path).map_err(|e| e.to_string())
+    git::unstage_file(&repo_path, &file_path).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn unstage_file_cmd(
+pub fn get_file_diff_hunks_cmd(
     file_path: String,
+    staged: bool,
     context_key: String,
     state: State<AppState>,
+) -> Result<git::FileDiff, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_diff_hunks(&repo_path, &file_path, staged).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stage_hunks_cmd(
+    file_path: String,
+    selection: Vec<git::HunkSelection>,
+    context_key: String,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stage")?;
+
+    git::stage_hunks(&repo_path, &file_path, &selection).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn unstage_hunks_cmd(
+    file_path: String,
+    selection: Vec<git::HunkSelection>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "unstage")?;
+
+    git::unstage_hunks(&repo_path, &file_path, &selection).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn discard_hunks_cmd(
+    file_path: String,
+    selection: Vec<git::HunkSelection>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::DiscardSnapshot, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "discard")?;
+
+    git::discard_hunks(&repo_path, &file_path, &selection).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn discard_files_cmd(
+    paths: Vec<String>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::DiscardSnapshot, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "discard")?;
+
+    git::discard_files(&repo_path, &paths).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn clean_untracked_cmd(
+    paths: Option<Vec<String>>,
+    include_ignored: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::DiscardSnapshot, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "clean")?;
+
+    git::clean_untracked(
+        &repo_path,
+        &paths.unwrap_or_default(),
+        include_ignored.unwrap_or(false),
+    )
+    .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_discard_snapshots_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::DiscardSnapshot>, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::unstage_file(&repo_path, &file_path).map_err(|e| e.to_string())
+    git::get_discard_snapshots(&repo_path).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn commit_cmd(
-    message: String,
+pub async fn undo_discard_cmd(
+    snapshot_id: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "undo discard")?;
+
+    git::undo_discard(&repo_path, &snapshot_id).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn delete_discard_snapshot_cmd(
+    snapshot_id: String,
     context_key: String,
     state: State<AppState>,
 ) -> Result<(), String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::commit(&repo_path, &message).map_err(|e| e.to_string())
+    git::delete_discard_snapshot(&repo_path, &snapshot_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn amend_commit_cmd(
+pub async fn commit_cmd(
     message: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "commit")?;
 
-    git::amend_commit(&repo_path, &message).map_err(|e| e.to_string())
+    git::commit(&repo_path, &message).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn amend_commit_cmd(
+    message: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "amend")?;
+
+    git::amend_commit(&repo_path, &message).map_err(locks::error_message)
 }
 
 #[tauri::command]
@@ -194,17 +379,223 @@ pub fn is_last_commit_pushed_cmd(
 }
 
 #[tauri::command]
-pub fn push_cmd(context_key: String, state: State<AppState>) -> Result<String, String> {
+pub async fn push_cmd(
+    options: Option<git::PushOptions>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "push")?;
+    let config = config::load_config().map_err(|e| e.to_string())?;
+
+    git::push(
+        &repo_path,
+        &options.unwrap_or_default(),
+        &config.protected_branches,
+    )
+    .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn pull_cmd(
+    options: Option<git::PullOptions>,
+    auto_stash: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::PullResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "pull")?;
+    let options = pull_options(&repo_path, options, auto_stash)?;
+
+    git::pull(&repo_path, &options).map_err(locks::error_message)
+}
+
+/// Fills in the repository's configured pull strategy when the caller did not pick one.
+fn pull_options(
+    repo_path: &Path,
+    options: Option<git::PullOptions>,
+    auto_stash: Option<bool>,
+) -> Result<git::PullOptions, String> {
+    let mut options = options.unwrap_or_default();
+    if options.strategy.is_none() {
+        let config = config::load_config().map_err(|e| e.to_string())?;
+        options.strategy = Some(config.pull_strategy_for(&repo_path.to_string_lossy()));
+    }
+    options.autostash |= auto_stash.unwrap_or(false);
+    Ok(options)
+}
+
+#[tauri::command]
+pub fn get_pull_strategy_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<git::PullStrategy, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
+    let config = config::load_config().map_err(|e| e.to_string())?;
 
-    git::push(&repo_path).map_err(|e| e.to_string())
+    Ok(config.pull_strategy_for(&repo_path.to_string_lossy()))
 }
 
+/// Sets the repository's default pull strategy; `None` falls back to the global default.
 #[tauri::command]
-pub fn pull_cmd(context_key: String, state: State<AppState>) -> Result<String, String> {
+pub fn set_pull_strategy_cmd(
+    strategy: Option<git::PullStrategy>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<(), String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::pull(&repo_path).map_err(|e| e.to_string())
+    config::update_pull_strategy(repo_path.to_string_lossy().to_string(), strategy)
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Background Jobs
+// ============================================================================
+
+/// Starts a push in the background and returns its job id.
+#[tauri::command]
+pub fn start_push_job_cmd(
+    options: Option<git::PushOptions>,
+    context_key: String,
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+    let config = config::load_config().map_err(|e| e.to_string())?;
+    let locks = state.locks.clone();
+    let lock_context = context_key.clone();
+
+    Ok(state
+        .jobs
+        .spawn(app_handle, "push", Some(context_key), move |control| {
+            let _lock = locks.acquire(
+                &repo_path,
+                "push",
+                Some(&lock_context),
+                Wait::UntilCancelled(control),
+            )?;
+            git::push_with_progress(
+                &repo_path,
+                &options.unwrap_or_default(),
+                &config.protected_branches,
+                control,
+            )
+        }))
+}
+
+#[tauri::command]
+pub fn start_pull_job_cmd(
+    options: Option<git::PullOptions>,
+    auto_stash: Option<bool>,
+    context_key: String,
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+    let options = pull_options(&repo_path, options, auto_stash)?;
+    let locks = state.locks.clone();
+    let lock_context = context_key.clone();
+
+    Ok(state
+        .jobs
+        .spawn(app_handle, "pull", Some(context_key), move |control| {
+            let _lock = locks.acquire(
+                &repo_path,
+                "pull",
+                Some(&lock_context),
+                Wait::UntilCancelled(control),
+            )?;
+            git::pull_with_progress(&repo_path, &options, control)
+        }))
+}
+
+#[tauri::command]
+pub fn start_fetch_prune_job_cmd(
+    remote: Option<String>,
+    context_key: String,
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+    let locks = state.locks.clone();
+    let lock_context = context_key.clone();
+
+    Ok(state
+        .jobs
+        .spawn(app_handle, "fetch", Some(context_key), move |control| {
+            let _lock = locks.acquire(
+                &repo_path,
+                "fetch",
+                Some(&lock_context),
+                Wait::UntilCancelled(control),
+            )?;
+            git::fetch_prune_with_progress(&repo_path, remote.as_deref(), control)
+        }))
+}
+
+#[tauri::command]
+pub fn start_clone_job_cmd(
+    url: String,
+    destination: String,
+    options: Option<git::CloneOptions>,
+    app_handle: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<String, String> {
+    Ok(state.jobs.spawn(app_handle, "clone", None, move |control| {
+        git::clone_repository_with_progress(
+            &url,
+            &destination,
+            &options.unwrap_or_default(),
+            control,
+        )
+    }))
+}
+
+#[tauri::command]
+pub fn cancel_job_cmd(job_id: String, state: State<AppState>) -> Result<bool, String> {
+    Ok(state.jobs.cancel(&job_id))
+}
+
+#[tauri::command]
+pub fn get_jobs_cmd(state: State<AppState>) -> Result<Vec<JobInfo>, String> {
+    Ok(state.jobs.list())
+}
+
+// ============================================================================
+// Repository Lock Commands
+// ============================================================================
+
+/// Running and queued write operations on the context's repository, from any window or tab.
+#[tauri::command]
+pub fn get_repo_operations_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<locks::RepoOperation>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    Ok(state.locks.operations(&repo_path))
+}
+
+#[tauri::command]
+pub fn get_index_lock_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Option<git::IndexLock>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_index_lock(&repo_path).map_err(|e| e.to_string())
+}
+
+/// Removes an `index.lock` left behind by a crashed git process. Runs under the repository's
+/// operation lock, so it never races with an operation started from the app.
+#[tauri::command]
+pub async fn remove_stale_index_lock_cmd(
+    force: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "remove index lock")?;
+
+    git::remove_stale_index_lock(&repo_path, force.unwrap_or(false)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -217,45 +608,60 @@ pub fn get_branches_cmd(
     git::get_branches(&repo_path).map_err(|e| e.to_string())
 }
 
+/// Fetches one remote, or all remotes when `remote` is omitted.
 #[tauri::command]
-pub fn fetch_prune_cmd(context_key: String, state: State<AppState>) -> Result<String, String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+pub async fn fetch_prune_cmd(
+    remote: Option<String>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "fetch")?;
 
-    git::fetch_prune(&repo_path).map_err(|e| e.to_string())
+    git::fetch_prune(&repo_path, remote.as_deref()).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn checkout_branch_cmd(
+pub async fn checkout_branch_cmd(
     branch_name: String,
+    auto_stash: Option<bool>,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "checkout")?;
+
+    if auto_stash.unwrap_or(false) {
+        return git::with_auto_stash(&repo_path, "checkout", || {
+            git::checkout_branch(&repo_path, &branch_name)
+        })
+        .map(|_| ())
+        .map_err(locks::error_message);
+    }
 
-    git::checkout_branch(&repo_path, &branch_name).map_err(|e| e.to_string())
+    git::checkout_branch(&repo_path, &branch_name).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn checkout_remote_branch_cmd(
+pub async fn checkout_remote_branch_cmd(
     remote_ref: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "checkout")?;
 
-    git::checkout_remote_branch(&repo_path, &remote_ref).map_err(|e| e.to_string())
+    git::checkout_remote_branch(&repo_path, &remote_ref).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn create_branch_cmd(
+pub async fn create_branch_cmd(
     name: String,
     from: Option<String>,
     push_to_remote: bool,
+    remote: Option<String>,
     checkout: Option<bool>,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "create branch")?;
 
     let should_checkout = checkout.unwrap_or(true); // Default: checkout for backward compatibility
     git::create_branch(
@@ -263,22 +669,115 @@ pub fn create_branch_cmd(
         &name,
         from.as_deref(),
         push_to_remote,
+        remote.as_deref(),
         should_checkout,
     )
-    .map_err(|e| e.to_string())
+    .map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn delete_branch_cmd(
+pub async fn delete_branch_cmd(
     name: String,
     force: bool,
     is_remote: bool,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "delete branch")?;
+
+    let config = config::load_config().map_err(|e| e.to_string())?;
+
+    git::delete_branch(
+        &repo_path,
+        &name,
+        force,
+        is_remote,
+        &config.protected_branches,
+    )
+    .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_stale_branches_cmd(
+    base: Option<String>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::StaleBranch>, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::delete_branch(&repo_path, &name, force, is_remote).map_err(|e| e.to_string())
+    let config = config::load_config().map_err(|e| e.to_string())?;
+
+    git::get_stale_branches(&repo_path, base.as_deref(), &config.protected_branches)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_stale_branches_cmd(
+    names: Vec<String>,
+    base: Option<String>,
+    force: Option<bool>,
+    dry_run: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::BranchCleanupResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "delete branches")?;
+
+    let config = config::load_config().map_err(|e| e.to_string())?;
+
+    git::delete_stale_branches(
+        &repo_path,
+        &names,
+        base.as_deref(),
+        force.unwrap_or(false),
+        dry_run.unwrap_or(false),
+        &config.protected_branches,
+    )
+    .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn rename_branch_cmd(
+    old_name: String,
+    new_name: String,
+    rename_remote: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "rename branch")?;
+
+    let config = config::load_config().map_err(|e| e.to_string())?;
+
+    git::rename_branch(
+        &repo_path,
+        &old_name,
+        &new_name,
+        rename_remote.unwrap_or(false),
+        &config.protected_branches,
+    )
+    .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn set_branch_upstream_cmd(
+    branch: String,
+    upstream: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "set upstream")?;
+
+    git::set_branch_upstream(&repo_path, &branch, &upstream).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn unset_branch_upstream_cmd(
+    branch: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "unset upstream")?;
+
+    git::unset_branch_upstream(&repo_path, &branch).map_err(locks::error_message)
 }
 
 // ============================================================================
@@ -286,61 +785,103 @@ pub fn delete_branch_cmd(
 // ============================================================================
 
 #[tauri::command]
-pub fn reset_cmd(
+pub async fn reset_cmd(
     hash: String,
     mode: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "reset")?;
 
-    git::reset(&repo_path, &hash, &mode).map_err(|e| e.to_string())
+    git::reset(&repo_path, &hash, &mode).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn cherry_pick_cmd(
+pub async fn cherry_pick_cmd(
     hash: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "cherry-pick")?;
 
-    git::cherry_pick(&repo_path, &hash).map_err(|e| e.to_string())
+    git::cherry_pick(&repo_path, &hash).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn revert_cmd(
+pub async fn revert_cmd(
     hash: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "revert")?;
 
-    git::revert(&repo_path, &hash).map_err(|e| e.to_string())
+    git::revert(&repo_path, &hash).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn checkout_commit_cmd(
+pub async fn checkout_commit_cmd(
     hash: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "checkout")?;
 
-    git::checkout_commit(&repo_path, &hash).map_err(|e| e.to_string())
+    git::checkout_commit(&repo_path, &hash).map_err(locks::error_message)
 }
 
 #[tauri::command]
-pub fn create_tag_cmd(
+pub async fn create_tag_cmd(
     name: String,
     hash: String,
     message: Option<String>,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "create tag")?;
+
+    git::create_tag(&repo_path, &name, &hash, message.as_deref()).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_tags_cmd(context_key: String, state: State<AppState>) -> Result<Vec<git::Tag>, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::create_tag(&repo_path, &name, &hash, message.as_deref()).map_err(|e| e.to_string())
+    git::get_tags(&repo_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn show_tag_cmd(
+    name: String,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<git::TagDetails, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::show_tag(&repo_path, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_tag_cmd(
+    name: String,
+    remote: Option<String>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "delete tag")?;
+
+    git::delete_tag(&repo_path, &name, remote.as_deref()).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn push_tags_cmd(
+    name: Option<String>,
+    remote: Option<String>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "push tags")?;
+
+    git::push_tags(&repo_path, remote.as_deref(), name.as_deref()).map_err(locks::error_message)
 }
 
 #[tauri::command]
@@ -354,6 +895,17 @@ pub fn get_commit_diff_cmd(
     git::get_commit_diff(&repo_path, &hash).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn get_commit_diff_files_cmd(
+    hash: String,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::FileDiff>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_commit_diff_files(&repo_path, &hash).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn merge_preview_cmd(
     source: String,
@@ -367,15 +919,27 @@ pub fn merge_preview_cmd(
 }
 
 #[tauri::command]
-pub fn merge_branch_cmd(
+pub async fn merge_branch_cmd(
     source: String,
     message: Option<String>,
+    auto_stash: Option<bool>,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<git::MergeResult, String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "merge")?;
+
+    if auto_stash.unwrap_or(false) {
+        return git::with_auto_stash(&repo_path, "merge", || {
+            git::merge_branch(&repo_path, &source, message.as_deref())
+        })
+        .map(|(mut result, pending_stash)| {
+            result.pending_stash = pending_stash;
+            result
+        })
+        .map_err(locks::error_message);
+    }
 
-    git::merge_branch(&repo_path, &source, message.as_deref()).map_err(|e| e.to_string())
+    git::merge_branch(&repo_path, &source, message.as_deref()).map_err(locks::error_message)
 }
 
 #[tauri::command]
@@ -410,26 +974,86 @@ pub fn parse_conflict_file_cmd(
 }
 
 #[tauri::command]
-pub fn resolve_conflict_file_cmd(
+pub async fn checkout_conflict_style_cmd(
+    file_path: String,
+    style: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::ConflictFile, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "checkout")?;
+
+    git::checkout_conflict_style(&repo_path, &file_path, &style).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn resolve_conflict_file_cmd(
     file_path: String,
     resolved_content: String,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "resolve conflict")?;
+
+    git::resolve_conflict_file(&repo_path, &file_path, &resolved_content)
+        .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn resolve_conflict_with_side_cmd(
+    file_path: String,
+    side: git::ConflictResolutionSide,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "resolve conflict")?;
+
+    git::resolve_conflict_with_side(&repo_path, &file_path, side).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn resolve_conflict_as_deleted_cmd(
+    file_path: String,
+    context_key: String,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "resolve conflict")?;
+
+    git::resolve_conflict_as_deleted(&repo_path, &file_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_lockfile_conflicts_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::LockfileConflict>, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
+    let config = config::load_config().map_err(|e| e.to_string())?;
 
-    git::resolve_conflict_file(&repo_path, &file_path, &resolved_content).map_err(|e| e.to_string())
+    git::get_lockfile_conflicts(&repo_path, &config.lockfile_rules).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn complete_merge_cmd(
+pub async fn resolve_lockfile_conflict_cmd(
+    file_path: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::LockfileResolution, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "resolve conflict")?;
+    let config = config::load_config().map_err(|e| e.to_string())?;
+
+    git::resolve_lockfile_conflict(&repo_path, &file_path, &config.lockfile_rules)
+        .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn complete_merge_cmd(
     message: Option<String>,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "merge")?;
 
-    git::complete_merge(&repo_path, message.as_deref()).map_err(|e| e.to_string())
+    git::complete_merge(&repo_path, message.as_deref()).map_err(locks::error_message)
 }
 
 #[tauri::command]
@@ -447,12 +1071,91 @@ pub fn compare_branches_cmd(
 #[tauri::command]
 pub fn get_commit_log(
     limit: usize,
+    query: Option<git::LogQuery>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<git::LogPage, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_log(&repo_path, limit, &query.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_file_history_cmd(
+    file_path: String,
+    limit: usize,
+    revision: Option<String>,
+    cursor: Option<String>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<git::FileHistoryPage, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_file_history(
+        &repo_path,
+        &file_path,
+        limit,
+        revision.as_deref(),
+        cursor.as_deref(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_blame_cmd(
+    file_path: String,
+    options: Option<git::BlameOptions>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::BlameLine>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_blame(&repo_path, &file_path, &options.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_file_at_revision_cmd(
+    file_path: String,
+    revision: String,
     context_key: String,
     state: State<AppState>,
-) -> Result<Vec<git::CommitInfo>, String> {
+) -> Result<git::FileAtRevision, String> {
     let repo_path = get_repo_path(&state, &context_key)?;
 
-    git::get_log(&repo_path, limit).map_err(|e| e.to_string())
+    git::get_file_at_revision(&repo_path, &file_path, &revision).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_paths_cmd(
+    source: String,
+    paths: Vec<String>,
+    worktree: Option<bool>,
+    staged: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "restore")?;
+
+    git::restore_paths(
+        &repo_path,
+        &source,
+        &paths,
+        worktree.unwrap_or(true),
+        staged.unwrap_or(false),
+    )
+    .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_commit_graph_cmd(
+    limit: usize,
+    all_refs: Option<bool>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<git::CommitGraph, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_commit_graph(&repo_path, limit, all_refs.unwrap_or(false)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -465,6 +1168,70 @@ pub fn get_remote_origin_url_cmd(
     git::get_remote_origin_url(&repo_path).map_err(|e| e.to_string())
 }
 
+// ============================================================================
+// Remote Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_remotes_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::Remote>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_remotes(&repo_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_remote_cmd(
+    name: String,
+    url: String,
+    fetch: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "add remote")?;
+
+    git::add_remote(&repo_path, &name, &url, fetch.unwrap_or(false)).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn rename_remote_cmd(
+    old_name: String,
+    new_name: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "rename remote")?;
+
+    git::rename_remote(&repo_path, &old_name, &new_name).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn remove_remote_cmd(
+    name: String,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "remove remote")?;
+
+    git::remove_remote(&repo_path, &name).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn set_remote_url_cmd(
+    name: String,
+    url: String,
+    push: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "set remote url")?;
+
+    git::set_remote_url(&repo_path, &name, &url, push.unwrap_or(false))
+        .map_err(locks::error_message)
+}
+
 #[tauri::command]
 pub fn get_commit_shortstat_cmd(
     hash: String,
@@ -588,6 +1355,188 @@ pub fn authenticate_gh_cmd() -> Result<setup::AuthResult, String> {
     setup::authenticate_gh_via_browser().map_err(|e| e.to_string())
 }
 
+// ============================================================================
+// Stash Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_stashes_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::StashEntry>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_stashes(&repo_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_stash_cmd(
+    options: git::StashCreateOptions,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stash")?;
+
+    git::create_stash(&repo_path, &options).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn apply_stash_cmd(
+    index: usize,
+    restore_index: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::StashApplyResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stash apply")?;
+
+    git::apply_stash(&repo_path, index, restore_index.unwrap_or(false))
+        .map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn pop_stash_cmd(
+    index: usize,
+    restore_index: Option<bool>,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::StashApplyResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stash pop")?;
+
+    git::pop_stash(&repo_path, index, restore_index.unwrap_or(false)).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn drop_stash_cmd(
+    index: usize,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "stash drop")?;
+
+    git::drop_stash(&repo_path, index).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_stash_diff_cmd(
+    index: usize,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::FileDiff>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_stash_diff(&repo_path, index).map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Rebase Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_rebase_todo_cmd(
+    base: Option<String>,
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Vec<git::RebaseTodoItem>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_rebase_todo(&repo_path, base.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn start_interactive_rebase_cmd(
+    options: git::InteractiveRebaseOptions,
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::RebaseResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "rebase")?;
+
+    git::start_interactive_rebase(&repo_path, &options).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn continue_rebase_cmd(
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::RebaseResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "rebase")?;
+
+    git::continue_rebase(&repo_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn skip_rebase_cmd(
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::RebaseResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "rebase")?;
+
+    git::skip_rebase(&repo_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn abort_rebase_cmd(
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::RebaseResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "rebase")?;
+
+    git::abort_rebase(&repo_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub fn get_rebase_status_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<git::RebaseStatus, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_rebase_status(&repo_path).map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Operation in Progress Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_operation_in_progress_cmd(
+    context_key: String,
+    state: State<AppState>,
+) -> Result<Option<git::OperationInProgress>, String> {
+    let repo_path = get_repo_path(&state, &context_key)?;
+
+    git::get_operation_in_progress(&repo_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn continue_operation_cmd(
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::OperationResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "continue")?;
+
+    git::continue_operation(&repo_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn abort_operation_cmd(
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::OperationResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "abort")?;
+
+    git::abort_operation(&repo_path).map_err(locks::error_message)
+}
+
+#[tauri::command]
+pub async fn skip_operation_cmd(
+    context_key: String,
+    state: State<'_, AppState>,
+) -> Result<git::OperationResult, String> {
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "skip")?;
+
+    git::skip_operation(&repo_path).map_err(locks::error_message)
+}
+
 // ============================================================================
 // Worktree Commands
 // ============================================================================
@@ -603,15 +1552,15 @@ pub fn get_worktrees_cmd(
 }
 
 #[tauri::command]
-pub fn remove_worktree_cmd(
+pub async fn remove_worktree_cmd(
     worktree_path: String,
     force: bool,
     context_key: String,
-    state: State<AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let repo_path = get_repo_path(&state, &context_key)?;
+    let (repo_path, _lock) = lock_repo(&state, &context_key, "remove worktree")?;
 
-    git::remove_worktree(&repo_path, &worktree_path, force).map_err(|e| e.to_string())
+    git::remove_worktree(&repo_path, &worktree_path, force).map_err(locks::error_message)
 }
 
 #[tauri::command]
@@ -670,6 +1619,11 @@ pub fn open_worktree_window_cmd(
 // ============================================================================
 
 #[tauri::command]
-pub fn clone_repository_cmd(url: String, destination: String) -> Result<String, String> {
-    git::clone_repository(&url, &destination).map_err(|e| e.to_string())
+pub fn clone_repository_cmd(
+    url: String,
+    destination: String,
+    options: Option<git::CloneOptions>,
+) -> Result<String, String> {
+    git::clone_repository(&url, &destination, &options.unwrap_or_default())
+        .map_err(|e| e.to_string())
 }
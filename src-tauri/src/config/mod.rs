@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use std::sync::{Mutex, OnceLock};
 use anyhow::{Result, Context, anyhow};
 use crate::ai::{AiConfig, AiProvider};
+use crate::git::{ConflictResolutionSide, LockfileRule, PullStrategy};
 
 const APP_CONFIG_DIR: &str = "everydaygit";
 const LEGACY_APP_CONFIG_DIR: &str = "gitflow-ai";
@@ -247,12 +248,74 @@ pub struct AppConfig {
     pub theme: String,
     #[serde(default = "default_ui_language")]
     pub ui_language: String,
+    #[serde(default = "default_lockfile_rules")]
+    pub lockfile_rules: Vec<LockfileRule>,
+    /// Branch names (or patterns like "release/*") that may not be force-pushed or deleted
+    #[serde(default = "default_protected_branches")]
+    pub protected_branches: Vec<String>,
+    /// Pull strategy for repositories without their own setting
+    #[serde(default)]
+    pub default_pull_strategy: PullStrategy,
+    /// Pull strategy per repository path
+    #[serde(default)]
+    pub pull_strategies: HashMap<String, PullStrategy>,
+}
+
+impl AppConfig {
+    pub fn pull_strategy_for(&self, repo_path: &str) -> PullStrategy {
+        self.pull_strategies
+            .get(repo_path)
+            .copied()
+            .unwrap_or(self.default_pull_strategy)
+    }
 }
 
 fn default_ui_language() -> String {
     "pt-BR".to_string()
 }
 
+/// Only commands that rewrite the lockfile itself: no full installs and no version
+/// upgrades. Tools without such a mode that works across their versions (yarn, bun,
+/// poetry) keep our side and leave regeneration to the user.
+///
+/// Cargo is in that group too: `cargo generate-lockfile` re-resolves every version, and
+/// `cargo metadata` only rewrites Cargo.lock as a side effect while printing the whole
+/// dependency graph. The next cargo build updates the kept lockfile anyway.
+fn default_lockfile_rules() -> Vec<LockfileRule> {
+    [
+        (
+            "package-lock.json",
+            Some("npm install --package-lock-only --ignore-scripts"),
+        ),
+        (
+            "npm-shrinkwrap.json",
+            Some("npm install --package-lock-only --ignore-scripts"),
+        ),
+        ("yarn.lock", None),
+        (
+            "pnpm-lock.yaml",
+            Some("pnpm install --lockfile-only --ignore-scripts"),
+        ),
+        ("bun.lock", None),
+        ("bun.lockb", None),
+        ("Cargo.lock", None),
+        ("composer.lock", Some("composer update --lock --no-scripts")),
+        ("Gemfile.lock", Some("bundle lock")),
+        ("poetry.lock", None),
+    ]
+    .into_iter()
+    .map(|(pattern, command)| LockfileRule {
+        pattern: pattern.to_string(),
+        side: ConflictResolutionSide::Ours,
+        command: command.map(str::to_string),
+    })
+    .collect()
+}
+
+fn default_protected_branches() -> Vec<String> {
+    vec!["main".to_string(), "master".to_string()]
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitPreferences {
     pub language: String,
@@ -279,6 +342,10 @@ impl Default for AppConfig {
             last_repo_path: None,
             theme: "dark".to_string(),
             ui_language: default_ui_language(),
+            lockfile_rules: default_lockfile_rules(),
+            protected_branches: default_protected_branches(),
+            default_pull_strategy: PullStrategy::default(),
+            pull_strategies: HashMap::new(),
         }
     }
 }
@@ -383,6 +450,20 @@ pub fn update_commit_preferences(preferences: CommitPreferences) -> Result<()> {
     save_config(&config)
 }
 
+/// Sets (or with `None`, clears) the pull strategy of one repository.
+pub fn update_pull_strategy(repo_path: String, strategy: Option<PullStrategy>) -> Result<()> {
+    let mut config = load_config_raw()?;
+    match strategy {
+        Some(strategy) => {
+            config.pull_strategies.insert(repo_path, strategy);
+        }
+        None => {
+            config.pull_strategies.remove(&repo_path);
+        }
+    }
+    save_config(&config)
+}
+
 pub fn update_last_repo(repo_path: String) -> Result<()> {
     let mut config = load_config_raw()?;
     config.last_repo_path = Some(repo_path);
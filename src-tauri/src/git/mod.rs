@@ -1,9 +1,13 @@
 use anyhow::{anyhow, Context, Result};
-use chrono::{Datelike, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
 
 /// Creates a git Command with extended PATH for macOS GUI apps.
 /// GUI apps on macOS don't inherit the terminal PATH, so we need to add
@@ -11,7 +15,31 @@ use std::process::Command;
 /// This is necessary for git hooks (like Husky) that call node/npx.
 fn git_command() -> Command {
     let mut cmd = Command::new("git");
+    cmd.env("PATH", extended_path());
+    cmd
+}
+
+/// Creates a shell Command for user-configured command lines, with the same
+/// extended PATH as `git_command` so package managers are found from GUI launches.
+fn shell_command(command_line: &str) -> Command {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command_line]);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command_line]);
+        cmd
+    };
+
+    cmd.env("PATH", extended_path());
+    cmd
+}
 
+fn extended_path() -> String {
     // Build extended PATH with common tool locations
     let current_path = std::env::var("PATH").unwrap_or_default();
     let extra_paths = [
@@ -28,15 +56,68 @@ fn git_command() -> Command {
         }
     }
 
-    cmd.env("PATH", extended_path);
-    cmd
+    extended_path
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileStatus {
     pub path: String,
     pub status: String,
     pub staged: bool,
+    /// Index state of the file ("Modified", "Added", "Renamed"...), regardless of which entry this is
+    #[serde(default)]
+    pub index_status: Option<String>,
+    /// Working tree state of the file, regardless of which entry this is
+    #[serde(default)]
+    pub worktree_status: Option<String>,
+    /// Source path of a rename or copy
+    #[serde(default)]
+    pub orig_path: Option<String>,
+    #[serde(default)]
+    pub conflict: Option<ConflictKind>,
+    #[serde(default)]
+    pub submodule: Option<SubmoduleStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmoduleStatus {
+    /// The submodule checkout is at a different commit than recorded
+    pub commit_changed: bool,
+    pub has_tracked_changes: bool,
+    pub has_untracked_changes: bool,
+}
+
+/// One parsed `--progress` line, e.g. "Receiving objects:  45% (9/20), 1.2 MiB | 800 KiB/s".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitProgress {
+    /// "Counting objects", "Receiving objects", "Resolving deltas"...
+    pub phase: String,
+    /// Reported by the remote side ("remote: ..." lines)
+    pub remote: bool,
+    pub percent: Option<u8>,
+    pub current: Option<u64>,
+    pub total: Option<u64>,
+    /// Transfer size and rate, e.g. "1.2 MiB | 800 KiB/s"
+    pub throughput: Option<String>,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloneOptions {
+    /// Branch or tag to check out instead of the remote's HEAD
+    pub branch: Option<String>,
+    /// Shallow clone truncated to this many commits
+    pub depth: Option<u32>,
+    /// Fetch only the history of the checked out branch
+    #[serde(default)]
+    pub single_branch: bool,
+    #[serde(default)]
+    pub recurse_submodules: bool,
+    /// Partial clone filter, e.g. "blob:none" or "tree:0"
+    pub filter: Option<String>,
+    /// Sparse checkout patterns (directories, or gitignore-style patterns); full checkout when empty
+    #[serde(default)]
+    pub sparse_paths: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +125,168 @@ pub struct Branch {
     pub name: String,
     pub current: bool,
     pub remote: bool,
+    /// Tracking branch, e.g. "origin/main"
+    #[serde(default)]
+    pub upstream: Option<String>,
+    /// The upstream is configured but no longer exists (deleted on the remote and pruned)
+    #[serde(default)]
+    pub upstream_gone: bool,
+    /// Commits not pushed to the upstream yet
+    #[serde(default)]
+    pub ahead: u32,
+    /// Commits on the upstream not pulled yet
+    #[serde(default)]
+    pub behind: u32,
+    /// This is the repository's default branch (see `get_default_branch`)
+    #[serde(default)]
+    pub is_default: bool,
+    /// Commits ahead of / behind the default branch; `None` when there is no default branch
+    #[serde(default)]
+    pub default_ahead: Option<u32>,
+    #[serde(default)]
+    pub default_behind: Option<u32>,
+    /// Every commit of the branch is already in the default branch
+    #[serde(default)]
+    pub merged: bool,
+    #[serde(default)]
+    pub last_commit: Option<BranchCommit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchCommit {
+    pub hash: String,
+    pub subject: String,
+    pub author: String,
+    pub date: String,
+}
+
+/// A local branch that looks safe to clean up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleBranch {
+    pub name: String,
+    /// Its upstream was deleted on the remote (e.g. after the pull request was merged)
+    pub upstream_gone: bool,
+    /// Every commit of the branch is already in the base branch
+    pub merged: bool,
+    pub last_commit: Option<BranchCommit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedBranch {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchCleanupResult {
+    pub dry_run: bool,
+    /// Branches deleted, or that would be deleted on a dry run
+    pub deleted: Vec<StaleBranch>,
+    pub skipped: Vec<SkippedBranch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Remote {
+    pub name: String,
+    pub fetch_url: String,
+    /// Where pushes go; the fetch URL unless `pushurl` is configured (possibly several)
+    pub push_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    /// Commit the tag points at (peeled through the tag object for annotated tags)
+    pub target: String,
+    /// Annotated tags are objects with a tagger and message; lightweight tags are plain refs
+    pub annotated: bool,
+    pub tagger: Option<String>,
+    pub tagger_email: Option<String>,
+    /// Tagging date for annotated tags, commit date for lightweight ones
+    pub date: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagDetails {
+    pub tag: Tag,
+    pub commit: CommitInfo,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PushOptions {
+    /// Remote to push to; defaults to the branch's push remote, then "origin"
+    pub remote: Option<String>,
+    /// Local branch to push; defaults to the current branch
+    pub branch: Option<String>,
+    /// Remote branch to update when it differs from the local branch name
+    pub remote_branch: Option<String>,
+    /// Overwrite the remote branch unless someone else pushed to it since our last fetch.
+    /// Needed after rewriting pushed history (amend, reset, rebase).
+    #[serde(default)]
+    pub force_with_lease: bool,
+    /// Push all local tags along with the branch
+    #[serde(default)]
+    pub tags: bool,
+    /// Push only this tag (e.g. one made with `create_tag`) instead of a branch
+    pub tag: Option<String>,
+    /// Object the remote ref must still point at for `force_with_lease`. Branches default
+    /// to their remote-tracking branch; moving a tag requires it.
+    pub expected_oid: Option<String>,
+    /// Report what would be pushed without updating the remote
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PullOptions {
+    /// Remote to pull from; defaults to the branch's upstream remote
+    pub remote: Option<String>,
+    /// Remote branch to integrate; defaults to the upstream branch
+    pub branch: Option<String>,
+    /// How to integrate remote changes; fast-forward only when not set
+    pub strategy: Option<PullStrategy>,
+    /// Stash local changes before pulling and reapply them afterwards
+    #[serde(default)]
+    pub autostash: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullStrategy {
+    /// Fast-forward when possible, otherwise create a merge commit
+    Merge,
+    /// Replay local commits on top of the remote branch
+    Rebase,
+    /// Only fast-forward; diverged branches are reported instead of merged
+    #[default]
+    FfOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+    Rebased,
+    /// Stopped on conflicts; see `PullResult::operation`
+    Conflicts,
+    /// Fast-forward only, but local and remote have both moved on
+    Diverged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullResult {
+    pub outcome: PullOutcome,
+    pub strategy: PullStrategy,
+    pub summary: String,
+    /// The merge or rebase left in progress on conflicts. Resolve it with the conflict
+    /// commands, then continue or abort it like any other operation in progress.
+    pub operation: Option<OperationInProgress>,
+    /// Autostashed local changes couldn't be re-applied and remain in the latest stash
+    #[serde(default)]
+    pub autostash_pending: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +295,185 @@ pub struct CommitInfo {
     pub message: String,
     pub author: String,
     pub date: String,
+    #[serde(default)]
+    pub author_email: String,
+    #[serde(default)]
+    pub committer: String,
+    #[serde(default)]
+    pub committer_email: String,
+    #[serde(default)]
+    pub committer_date: String,
+    #[serde(default)]
+    pub parents: Vec<String>,
+    #[serde(default)]
+    pub refs: Vec<CommitRef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitRefKind {
+    /// Detached HEAD pointing at the commit
+    Head,
+    Branch,
+    RemoteBranch,
+    Tag,
+    Other,
+}
+
+/// A decoration (branch, tag, HEAD) pointing at a commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitRef {
+    /// Short name, e.g. "main", "origin/main" or "v1.0.0"
+    pub name: String,
+    /// Full ref name, e.g. "refs/heads/main"
+    pub full_name: String,
+    pub kind: CommitRefKind,
+    /// The branch HEAD is attached to
+    pub is_head: bool,
+}
+
+/// A segment of the graph drawn between a row and the row below it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from_column: usize,
+    pub to_column: usize,
+    pub color: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphRow {
+    pub commit: CommitInfo,
+    pub column: usize,
+    pub color: usize,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitGraph {
+    pub rows: Vec<GraphRow>,
+    /// Widest row, so the UI can size the graph column up front
+    pub max_columns: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PickaxeMode {
+    /// `-S`: commits that change the number of occurrences of the text
+    #[default]
+    Text,
+    /// `-G`: commits whose diff has added/removed lines matching the regex
+    Regex,
+}
+
+/// Filters for `get_log`. Everything is optional; the default is HEAD's history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    /// Opaque cursor from a previous `LogPage::next_cursor`; overrides `revision` and `all_refs`.
+    pub cursor: Option<String>,
+    /// Branch, tag, commit or range (`main`, `v1.0..HEAD`, `main...feature`)
+    pub revision: Option<String>,
+    pub all_refs: bool,
+    pub author: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub paths: Vec<String>,
+    /// Message search (`--grep`)
+    pub grep: Option<String>,
+    pub ignore_case: bool,
+    /// Content search (`-S` / `-G`)
+    pub pickaxe: Option<String>,
+    pub pickaxe_mode: PickaxeMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPage {
+    pub commits: Vec<CommitInfo>,
+    /// Pass back as `LogQuery::cursor` to load the next page; `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHistoryEntry {
+    pub commit: CommitInfo,
+    /// Path of the file in this commit (changes across renames)
+    pub path: String,
+    /// Previous path when this commit renamed or copied the file
+    pub old_path: Option<String>,
+    pub change: Option<FileChangeKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHistoryPage {
+    pub entries: Vec<FileHistoryEntry>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscardKind {
+    Files,
+    Hunks,
+    Untracked,
+}
+
+/// Working tree content saved before a discard, so it can be put back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscardSnapshot {
+    pub id: String,
+    pub created_at: String,
+    pub kind: DiscardKind,
+    pub files: Vec<SnapshotFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub path: String,
+    /// False when the file was missing from the working tree (a discarded deletion)
+    pub existed: bool,
+    pub executable: bool,
+    pub symlink_target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAtRevision {
+    pub path: String,
+    pub revision: String,
+    /// False when the path is not present at that revision
+    pub exists: bool,
+    pub blob: Option<String>,
+    pub mode: Option<String>,
+    pub size: Option<u64>,
+    pub is_binary: bool,
+    /// Text content; `None` for binary or missing files
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlameOptions {
+    /// Blame the file as of this revision instead of the working tree
+    pub revision: Option<String>,
+    pub ignore_whitespace: bool,
+    /// Skip the commits listed in `.git-blame-ignore-revs` at the repository root
+    pub use_ignore_revs_file: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    pub line_number: usize,
+    /// Line number in the commit that introduced the line
+    pub original_line: usize,
+    /// Path of the file in that commit
+    pub original_path: String,
+    pub hash: String,
+    pub author: String,
+    pub author_email: String,
+    pub date: String,
+    pub summary: String,
+    /// Line not committed yet (working tree changes)
+    pub uncommitted: bool,
+    pub content: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,7 +483,7 @@ pub struct CommitShortStat {
     pub deletions: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RepoStatus {
     pub files: Vec<FileStatus>,
     pub current_branch: String,
@@ -69,6 +491,12 @@ pub struct RepoStatus {
     pub behind: u32,
     pub insertions: u32,
     pub deletions: u32,
+    #[serde(default)]
+    pub upstream: Option<String>,
+    #[serde(default)]
+    pub head_commit: Option<String>,
+    #[serde(default)]
+    pub detached: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,6 +545,9 @@ pub struct MergeResult {
     pub fast_forward: bool,
     pub summary: String,
     pub conflicts: Vec<String>,
+    /// Auto-stash left in place because the merge is still in progress.
+    #[serde(default)]
+    pub pending_stash: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,8 +563,11 @@ pub struct ConflictHunk {
     pub id: usize,
     pub ours_content: String,
     pub theirs_content: String,
+    /// Common ancestor version, from diff3/zdiff3 markers or index stage 1.
+    pub base_content: Option<String>,
     pub ours_label: String,
     pub theirs_label: String,
+    pub base_label: Option<String>,
     pub start_line: usize,
     pub end_line: usize,
     pub context_before: Vec<String>,
@@ -146,6 +580,73 @@ pub struct ConflictFile {
     pub conflicts: Vec<ConflictHunk>,
     pub content: String,
     pub is_binary: bool,
+    pub kind: Option<ConflictKind>,
+    pub base: ConflictSide,
+    pub ours: ConflictSide,
+    pub theirs: ConflictSide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictKind {
+    BothModified,
+    BothAdded,
+    BothDeleted,
+    DeletedByUs,
+    DeletedByThem,
+    AddedByUs,
+    AddedByThem,
+}
+
+/// One side (index stage) of a conflicted path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflictSide {
+    /// The path has no entry for this stage (deleted on this side, or absent from the base).
+    pub deleted: bool,
+    pub blob: Option<String>,
+    pub mode: Option<String>,
+    pub size: Option<u64>,
+    pub is_binary: bool,
+    /// Path this side renamed the file from, relative to the merge base.
+    pub renamed_from: Option<String>,
+    /// Path this side renamed the file to, when it differs from the conflicted path.
+    pub renamed_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictResolutionSide {
+    Ours,
+    Theirs,
+}
+
+/// How to resolve conflicts in a generated lockfile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockfileRule {
+    /// File name to match (e.g. "package-lock.json"), or a repo-relative path when it contains '/'.
+    pub pattern: String,
+    pub side: ConflictResolutionSide,
+    /// Regeneration command, run through the shell from the lockfile's directory.
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockfileConflict {
+    pub path: String,
+    pub rule: LockfileRule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockfileResolution {
+    pub path: String,
+    pub side: ConflictResolutionSide,
+    pub command: Option<String>,
+    /// False when the regeneration command failed; the file is then left as taken from `side`.
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub output: String,
+    /// Other files the regeneration command changed (e.g. a second lockfile).
+    pub changed_files: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,9 +656,203 @@ pub struct Worktree {
     pub is_main: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Addition,
+    Deletion,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    /// The line is followed by "\ No newline at end of file".
+    pub no_newline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChangeKind {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// New path, or the old path when the file was deleted.
+    pub path: String,
+    /// `None` when the file was added.
+    pub old_path: Option<String>,
+    /// `None` when the file was deleted.
+    pub new_path: Option<String>,
+    pub change: FileChangeKind,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    /// Rename/copy similarity percentage.
+    pub similarity: Option<u32>,
+    pub is_binary: bool,
+    pub hunks: Vec<DiffHunk>,
+    /// Raw `diff --git` header lines, kept to rebuild partial patches.
+    #[serde(skip)]
+    pub header: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    pub index: usize,
+    /// "stash@{n}"
+    pub reference: String,
+    pub hash: String,
+    pub message: String,
+    pub branch: Option<String>,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StashCreateOptions {
+    pub message: Option<String>,
+    #[serde(default)]
+    pub include_untracked: bool,
+    #[serde(default)]
+    pub keep_index: bool,
+    /// Stash only these paths (all changes when empty).
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashApplyResult {
+    pub summary: String,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RebaseAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseTodoItem {
+    pub action: RebaseAction,
+    pub hash: String,
+    #[serde(default)]
+    pub subject: String,
+    /// New message for `reword`, or the combined message for `squash`.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveRebaseOptions {
+    /// Commit the todo list is replayed onto; `None` rebases from the root commit.
+    pub base: Option<String>,
+    /// Items in the order they should be applied.
+    pub todo: Vec<RebaseTodoItem>,
+    #[serde(default)]
+    pub autostash: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RebaseStatus {
+    pub in_progress: bool,
+    pub interactive: bool,
+    /// Branch being rebased (e.g. "feature/x"), `None` when detached.
+    pub head_name: Option<String>,
+    pub onto: Option<String>,
+    pub current_step: Option<usize>,
+    pub total_steps: Option<usize>,
+    /// Commit where the rebase stopped (edit, conflict).
+    pub stopped_at: Option<String>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseResult {
+    pub completed: bool,
+    pub summary: String,
+    pub status: RebaseStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationKind {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+    Am,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationInProgress {
+    pub kind: OperationKind,
+    pub current_step: Option<usize>,
+    pub total_steps: Option<usize>,
+    /// Branch the operation will update, when known.
+    pub head_name: Option<String>,
+    /// Commit being applied (cherry-pick, revert, rebase stop, merge source).
+    pub current_commit: Option<String>,
+    pub conflicts: Vec<String>,
+    pub can_continue: bool,
+    pub can_skip: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationResult {
+    pub completed: bool,
+    pub summary: String,
+    /// State after the step, `None` once the operation is finished.
+    pub operation: Option<OperationInProgress>,
+}
+
+/// Selects a hunk (by index in `FileDiff::hunks`) and optionally a subset of
+/// its lines (by index in `DiffHunk::lines`). `None` selects the whole hunk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkSelection {
+    pub hunk_index: usize,
+    pub line_indices: Option<Vec<usize>>,
+}
+
+/// Working tree status from `git status --porcelain=v2 -z`.
+///
+/// A file with both staged and unstaged changes is listed twice, once with
+/// `staged: true` and once with `staged: false`; conflicted files are listed
+/// once as unstaged with `conflict` set.
 pub fn get_status(repo_path: &PathBuf) -> Result<RepoStatus> {
+    // Without optional locks status doesn't write the refreshed index back, so it never
+    // holds index.lock while the app commits or stages (and doesn't wake the watcher)
     let output = Command::new("git")
-        .args(&["status", "--porcelain=v1", "--branch"])
+        .args([
+            "--no-optional-locks",
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+        ])
         .current_dir(repo_path)
         .output()
         .context("Failed to execute git status")?;
@@ -169,61 +864,7 @@ pub fn get_status(repo_path: &PathBuf) -> Result<RepoStatus> {
         );
     }
 
-    let status_output = String::from_utf8_lossy(&output.stdout);
-    let mut files = Vec::new();
-    let mut current_branch = String::from("unknown");
-    let mut ahead = 0;
-    let mut behind = 0;
-
-    for line in status_output.lines() {
-        if line.starts_with("##") {
-            // Parse branch info
-            let branch_info = line.trim_start_matches("## ");
-            if let Some(branch_name) = branch_info.split("...").next() {
-                current_branch = branch_name.to_string();
-            }
-
-            // Parse ahead/behind
-            if branch_info.contains("[ahead") {
-                if let Some(ahead_str) = branch_info.split("[ahead ").nth(1) {
-                    if let Some(num) = ahead_str.split("]").next() {
-                        ahead = num.trim_end_matches(']').parse().unwrap_or(0);
-                    }
-                }
-            }
-            if branch_info.contains("behind") {
-                if let Some(behind_str) = branch_info.split("behind ").nth(1) {
-                    if let Some(num) = behind_str.split("]").next() {
-                        behind = num.trim_end_matches(']').parse().unwrap_or(0);
-                    }
-                }
-            }
-        } else if line.len() >= 3 {
-            let status_code = &line[0..2];
-            let file_path = line[3..].to_string();
-
-            let (status, staged) = match status_code {
-                "M " => ("Modified", true),
-                " M" => ("Modified", false),
-                "MM" => ("Modified", true),
-                "A " => ("Added", true),
-                " A" => ("Added", false),
-                "D " => ("Deleted", true),
-                " D" => ("Deleted", false),
-                "?? " => ("Untracked", false),
-                "??" => ("Untracked", false),
-                "R " => ("Renamed", true),
-                "C " => ("Copied", true),
-                _ => ("Unknown", false),
-            };
-
-            files.push(FileStatus {
-                path: file_path,
-                status: status.to_string(),
-                staged,
-            });
-        }
-    }
+    let mut status = parse_status_v2(&String::from_utf8_lossy(&output.stdout));
 
     // Calculate total insertions and deletions (staged + unstaged)
     let mut insertions = 0;
@@ -275,13 +916,178 @@ pub fn get_status(repo_path: &PathBuf) -> Result<RepoStatus> {
     // The requirement is "total numbers of diffs". Conventionally, untracked files are not "diffs" yet.
     // So we will stick to staged + unstaged tracked changes.
 
-    Ok(RepoStatus {
+    status.insertions = insertions;
+    status.deletions = deletions;
+    Ok(status)
+}
+
+/// Parses `git status --porcelain=v2 --branch -z` output. Line counts are left at zero.
+fn parse_status_v2(status_output: &str) -> RepoStatus {
+    let mut files = Vec::new();
+    let mut current_branch = String::from("unknown");
+    let mut upstream = None;
+    let mut head_commit = None;
+    let mut detached = false;
+    let mut ahead = 0;
+    let mut behind = 0;
+
+    let mut entries = status_output.split('\0');
+    while let Some(entry) = entries.next() {
+        if let Some(header) = entry.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => head_commit = Some(value.to_string()),
+                "branch.head" if value == "(detached)" => {
+                    detached = true;
+                    current_branch = "HEAD (no branch)".to_string();
+                }
+                "branch.head" => current_branch = value.to_string(),
+                "branch.upstream" => upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split_whitespace() {
+                        if let Some(n) = count.strip_prefix('+') {
+                            ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = count.strip_prefix('-') {
+                            behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let kind = entry.chars().next().unwrap_or(' ');
+        match kind {
+            // "1 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <path>"
+            // "2 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <X><score> <path>\0<origPath>"
+            '1' | '2' => {
+                let field_count = if kind == '1' { 9 } else { 10 };
+                let fields: Vec<&str> = entry.splitn(field_count, ' ').collect();
+                if fields.len() < field_count {
+                    continue;
+                }
+                let orig_path = if kind == '2' {
+                    entries.next().map(str::to_string)
+                } else {
+                    None
+                };
+                push_status_entries(
+                    &mut files,
+                    fields[field_count - 1],
+                    fields[1],
+                    fields[2],
+                    orig_path,
+                );
+            }
+            // "u <XY> <sub> <m1> <m2> <m3> <mW> <h1> <h2> <h3> <path>"
+            'u' => {
+                let fields: Vec<&str> = entry.splitn(11, ' ').collect();
+                if fields.len() < 11 {
+                    continue;
+                }
+                files.push(FileStatus {
+                    path: fields[10].to_string(),
+                    status: "Conflicted".to_string(),
+                    staged: false,
+                    index_status: None,
+                    worktree_status: None,
+                    orig_path: None,
+                    conflict: unmerged_conflict_kind(fields[1]),
+                    submodule: parse_submodule_state(fields[2]),
+                });
+            }
+            '?' => files.push(FileStatus {
+                path: entry[2..].to_string(),
+                status: "Untracked".to_string(),
+                staged: false,
+                index_status: None,
+                worktree_status: Some("Untracked".to_string()),
+                orig_path: None,
+                conflict: None,
+                submodule: None,
+            }),
+            _ => {}
+        }
+    }
+
+    RepoStatus {
         files,
         current_branch,
         ahead,
         behind,
-        insertions,
-        deletions,
+        insertions: 0,
+        deletions: 0,
+        upstream,
+        head_commit,
+        detached,
+    }
+}
+
+/// Adds the staged and/or unstaged entries for an ordinary or renamed porcelain v2 record.
+fn push_status_entries(
+    files: &mut Vec<FileStatus>,
+    path: &str,
+    xy: &str,
+    submodule: &str,
+    orig_path: Option<String>,
+) {
+    let mut codes = xy.chars();
+    let index_status = codes.next().and_then(status_code_name);
+    let worktree_status = codes.next().and_then(status_code_name);
+    let submodule = parse_submodule_state(submodule);
+
+    for (status, staged) in [(&index_status, true), (&worktree_status, false)] {
+        let Some(status) = status else {
+            continue;
+        };
+        let is_copy_or_rename = status == "Renamed" || status == "Copied";
+        files.push(FileStatus {
+            path: path.to_string(),
+            status: status.clone(),
+            staged,
+            index_status: index_status.clone(),
+            worktree_status: worktree_status.clone(),
+            orig_path: orig_path.clone().filter(|_| is_copy_or_rename),
+            conflict: None,
+            submodule: submodule.clone(),
+        });
+    }
+}
+
+fn status_code_name(code: char) -> Option<String> {
+    let name = match code {
+        'M' => "Modified",
+        'T' => "TypeChanged",
+        'A' => "Added",
+        'D' => "Deleted",
+        'R' => "Renamed",
+        'C' => "Copied",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn unmerged_conflict_kind(xy: &str) -> Option<ConflictKind> {
+    match xy {
+        "UU" => Some(ConflictKind::BothModified),
+        "AA" => Some(ConflictKind::BothAdded),
+        "DD" => Some(ConflictKind::BothDeleted),
+        "DU" => Some(ConflictKind::DeletedByUs),
+        "UD" => Some(ConflictKind::DeletedByThem),
+        "AU" => Some(ConflictKind::AddedByUs),
+        "UA" => Some(ConflictKind::AddedByThem),
+        _ => None,
+    }
+}
+
+/// Parses the `<sub>` field: "N..." for regular files, "S<c><m><u>" for submodules.
+fn parse_submodule_state(field: &str) -> Option<SubmoduleStatus> {
+    let flags = field.strip_prefix('S')?.as_bytes();
+    Some(SubmoduleStatus {
+        commit_changed: flags.first() == Some(&b'C'),
+        has_tracked_changes: flags.get(1) == Some(&b'M'),
+        has_untracked_changes: flags.get(2) == Some(&b'U'),
     })
 }
 
@@ -392,11 +1198,624 @@ pub fn unstage_file(repo_path: &PathBuf, file_path: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn commit(repo_path: &PathBuf, message: &str) -> Result<()> {
-    let output = git_command()
-        .args(&["commit", "-m", message])
-        .current_dir(repo_path)
-        .output()
+// ============================================================================
+// Partial Staging (hunks and lines)
+// ============================================================================
+
+/// Parse unified diff output (as produced by `git diff`) into files, hunks and lines.
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut current: Option<FileDiff> = None;
+    let mut old_line = 0u32;
+    let mut new_line = 0u32;
+
+    let mut raw_lines: Vec<&str> = diff.split('\n').collect();
+    if raw_lines.last() == Some(&"") {
+        raw_lines.pop();
+    }
+
+    for line in raw_lines {
+        if line.starts_with("diff --git ") {
+            if let Some(file) = current.take() {
+                files.push(file);
+            }
+            let (old_path, new_path) = parse_diff_git_line_paths(line).unwrap_or_default();
+            let mut file = empty_file_diff(&new_path);
+            file.old_path = Some(old_path);
+            file.new_path = Some(new_path);
+            file.header.push(line.to_string());
+            current = Some(file);
+            continue;
+        }
+
+        let Some(file) = current.as_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            let Some((old_start, old_lines, new_start, new_lines)) = parse_hunk_header(line) else {
+                continue;
+            };
+            old_line = old_start;
+            new_line = new_start;
+            file.hunks.push(DiffHunk {
+                header: line.to_string(),
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(hunk) = file.hunks.last_mut() else {
+            // Still in the file header section
+            parse_diff_header_line(file, line);
+            file.header.push(line.to_string());
+            continue;
+        };
+
+        let (kind, content) = match line.chars().next() {
+            Some(' ') => (DiffLineKind::Context, &line[1..]),
+            Some('+') => (DiffLineKind::Addition, &line[1..]),
+            Some('-') => (DiffLineKind::Deletion, &line[1..]),
+            Some('\\') => {
+                if let Some(last) = hunk.lines.last_mut() {
+                    last.no_newline = true;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        let (old, new) = match kind {
+            DiffLineKind::Context => {
+                old_line += 1;
+                new_line += 1;
+                (Some(old_line - 1), Some(new_line - 1))
+            }
+            DiffLineKind::Addition => {
+                new_line += 1;
+                (None, Some(new_line - 1))
+            }
+            DiffLineKind::Deletion => {
+                old_line += 1;
+                (Some(old_line - 1), None)
+            }
+        };
+
+        hunk.lines.push(DiffLine {
+            kind,
+            content: content.to_string(),
+            old_line: old,
+            new_line: new,
+            no_newline: false,
+        });
+    }
+
+    if let Some(file) = current.take() {
+        files.push(file);
+    }
+
+    files
+}
+
+fn empty_file_diff(path: &str) -> FileDiff {
+    FileDiff {
+        path: path.to_string(),
+        old_path: Some(path.to_string()),
+        new_path: Some(path.to_string()),
+        change: FileChangeKind::Modified,
+        old_mode: None,
+        new_mode: None,
+        similarity: None,
+        is_binary: false,
+        hunks: Vec::new(),
+        header: Vec::new(),
+    }
+}
+
+/// Applies one extended header line ("new file mode", "rename from", "+++ b/x"...) to `file`.
+fn parse_diff_header_line(file: &mut FileDiff, line: &str) {
+    if let Some(mode) = line.strip_prefix("new file mode ") {
+        file.change = FileChangeKind::Added;
+        file.old_path = None;
+        file.new_mode = Some(mode.trim().to_string());
+    } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+        file.change = FileChangeKind::Deleted;
+        file.new_path = None;
+        file.old_mode = Some(mode.trim().to_string());
+    } else if let Some(mode) = line.strip_prefix("old mode ") {
+        file.old_mode = Some(mode.trim().to_string());
+    } else if let Some(mode) = line.strip_prefix("new mode ") {
+        file.new_mode = Some(mode.trim().to_string());
+    } else if let Some(index) = line.strip_prefix("index ") {
+        // "index abc..def 100644": the trailing mode is present when unchanged
+        if let Some((_, mode)) = index.split_once(' ') {
+            let mode = mode.trim().to_string();
+            file.old_mode.get_or_insert_with(|| mode.clone());
+            file.new_mode.get_or_insert(mode);
+        }
+    } else if let Some(value) = line.strip_prefix("similarity index ") {
+        file.similarity = value.trim().trim_end_matches('%').parse().ok();
+    } else if let Some(path) = line.strip_prefix("rename from ") {
+        file.change = FileChangeKind::Renamed;
+        file.old_path = Some(unquote_git_path(path));
+    } else if let Some(path) = line.strip_prefix("rename to ") {
+        file.change = FileChangeKind::Renamed;
+        file.new_path = Some(unquote_git_path(path));
+    } else if let Some(path) = line.strip_prefix("copy from ") {
+        file.change = FileChangeKind::Copied;
+        file.old_path = Some(unquote_git_path(path));
+    } else if let Some(path) = line.strip_prefix("copy to ") {
+        file.change = FileChangeKind::Copied;
+        file.new_path = Some(unquote_git_path(path));
+    } else if let Some(path) = line.strip_prefix("--- ") {
+        if path != "/dev/null" {
+            file.old_path = Some(strip_diff_prefix(&unquote_git_path(path), "a/"));
+        }
+    } else if let Some(path) = line.strip_prefix("+++ ") {
+        if path != "/dev/null" {
+            file.new_path = Some(strip_diff_prefix(&unquote_git_path(path), "b/"));
+        }
+    } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+        file.is_binary = true;
+    }
+
+    file.path = file
+        .new_path
+        .clone()
+        .or_else(|| file.old_path.clone())
+        .unwrap_or_default();
+}
+
+/// Parses "@@ -a,b +c,d @@ section" into (a, b, c, d).
+fn parse_hunk_header(line: &str) -> Option<(u32, u32, u32, u32)> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != "@@" {
+        return None;
+    }
+    let (old_start, old_lines) = parse_hunk_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_lines) = parse_hunk_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_lines, new_start, new_lines))
+}
+
+fn parse_hunk_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Extracts (old, new) paths from "diff --git a/old b/new". Later header lines
+/// refine them, but binary and mode-only changes have nothing else.
+fn parse_diff_git_line_paths(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix("diff --git ")?;
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let end = closing_quote_index(quoted)?;
+        let old_path = unquote_git_path(&rest[..end + 2]);
+        let new_path = unquote_git_path(rest[end + 2..].trim());
+        return Some((
+            strip_diff_prefix(&old_path, "a/"),
+            strip_diff_prefix(&new_path, "b/"),
+        ));
+    }
+
+    if rest.ends_with('"') {
+        let start = rest.find(" \"")?;
+        let new_path = unquote_git_path(&rest[start + 1..]);
+        return Some((
+            strip_diff_prefix(&rest[..start], "a/"),
+            strip_diff_prefix(&new_path, "b/"),
+        ));
+    }
+
+    // Unchanged paths may contain " b/", so prefer splitting in the middle
+    let middle = rest.len() / 2;
+    if rest.len() % 2 == 1 && rest.is_char_boundary(middle) {
+        let (old_path, new_path) = (&rest[..middle], &rest[middle + 1..]);
+        if let (Some(old), Some(new)) = (old_path.strip_prefix("a/"), new_path.strip_prefix("b/")) {
+            if old == new {
+                return Some((old.to_string(), new.to_string()));
+            }
+        }
+    }
+
+    let (old_path, new_path) = rest.split_once(" b/")?;
+    Some((strip_diff_prefix(old_path, "a/"), new_path.to_string()))
+}
+
+/// Index of the first unescaped `"` in a C-style quoted string (opening quote excluded).
+fn closing_quote_index(quoted: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, byte) in quoted.bytes().enumerate() {
+        match byte {
+            b'\\' if !escaped => escaped = true,
+            b'"' if !escaped => return Some(index),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn strip_diff_prefix(path: &str, prefix: &str) -> String {
+    path.strip_prefix(prefix).unwrap_or(path).to_string()
+}
+
+/// Decodes a C-style quoted path as emitted by git for unusual file names.
+fn unquote_git_path(path: &str) -> String {
+    let path = path.trim_end_matches('\t');
+    let Some(inner) = path
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return path.to_string();
+    };
+
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes().peekable();
+    while let Some(byte) = chars.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b'a') => bytes.push(0x07),
+            Some(b'b') => bytes.push(0x08),
+            Some(b'f') => bytes.push(0x0c),
+            Some(b'v') => bytes.push(0x0b),
+            Some(digit @ b'0'..=b'7') => {
+                let mut value = u32::from(digit - b'0');
+                for _ in 0..2 {
+                    match chars.peek() {
+                        Some(next @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(next - b'0');
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => bytes.push(other),
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Runs `git diff` with a stable output format and parses the result.
+fn run_structured_diff(repo_path: &Path, diff_args: &[&str]) -> Result<Vec<FileDiff>> {
+    run_structured_diff_command(repo_path, &["diff"], diff_args)
+}
+
+/// Runs a diff-producing command (`diff`, `show`, `stash show`...) with a
+/// stable output format and parses the result.
+fn run_structured_diff_command(
+    repo_path: &Path,
+    command: &[&str],
+    diff_args: &[&str],
+) -> Result<Vec<FileDiff>> {
+    let output = Command::new("git")
+        .args(["-c", "core.quotepath=false"])
+        .args(command)
+        .args([
+            "--no-color",
+            "--no-ext-diff",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+        ])
+        .args(diff_args)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("Failed to execute git {}", command.join(" ")))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git {} failed: {}",
+            command.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(parse_diff(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Returns the parsed diff of a single file (unstaged or staged).
+/// A file without changes yields a `FileDiff` with no hunks.
+pub fn get_diff_hunks(repo_path: &Path, file_path: &str, staged: bool) -> Result<FileDiff> {
+    let mut args = Vec::new();
+    if staged {
+        args.push("--cached");
+    }
+    args.push("--");
+    args.push(file_path);
+
+    Ok(run_structured_diff(repo_path, &args)?
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| empty_file_diff(file_path)))
+}
+
+/// Structured counterpart of `get_all_diff`.
+pub fn get_all_diff_files(repo_path: &Path, staged: bool) -> Result<Vec<FileDiff>> {
+    let mut args = vec!["-M"];
+    if staged {
+        args.push("--cached");
+    }
+
+    run_structured_diff(repo_path, &args)
+}
+
+/// Structured counterpart of `get_commit_diff`.
+pub fn get_commit_diff_files(repo_path: &Path, commit: &str) -> Result<Vec<FileDiff>> {
+    run_structured_diff(repo_path, &["-M", commit, "HEAD"])
+}
+
+/// Stage the selected hunks/lines of the unstaged changes of a file.
+pub fn stage_hunks(repo_path: &Path, file_path: &str, selection: &[HunkSelection]) -> Result<()> {
+    if is_merge_in_progress(repo_path) {
+        anyhow::bail!("Cannot stage files while a merge is in progress. Resolve conflicts first.");
+    }
+
+    // Untracked files don't show up in `git diff`: mark them as intent-to-add first
+    if !is_tracked(repo_path, file_path)? {
+        let output = Command::new("git")
+            .args(["add", "--intent-to-add", "--", file_path])
+            .current_dir(repo_path)
+            .output()
+            .context("Failed to execute git add --intent-to-add")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Git add --intent-to-add failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    let file_diff = get_diff_hunks(repo_path, file_path, false)?;
+    let patch = build_partial_patch(&file_diff, selection, false)?;
+    apply_patch(repo_path, &patch, &["--cached"])
+}
+
+/// Unstage the selected hunks/lines of the staged changes of a file.
+pub fn unstage_hunks(repo_path: &Path, file_path: &str, selection: &[HunkSelection]) -> Result<()> {
+    let file_diff = get_diff_hunks(repo_path, file_path, true)?;
+    let patch = build_partial_patch(&file_diff, selection, true)?;
+    apply_patch(repo_path, &patch, &["--cached", "--reverse"])
+}
+
+/// Discards the selected unstaged lines. The file is snapshotted first (see `undo_discard`).
+pub fn discard_hunks(
+    repo_path: &Path,
+    file_path: &str,
+    selection: &[HunkSelection],
+) -> Result<DiscardSnapshot> {
+    let file_diff = get_diff_hunks(repo_path, file_path, false)?;
+    let patch = build_partial_patch(&file_diff, selection, true)?;
+
+    let snapshot =
+        create_discard_snapshot(repo_path, DiscardKind::Hunks, &[file_path.to_string()])?;
+    if let Err(err) = apply_patch(repo_path, &patch, &["--reverse"]) {
+        let _ = delete_discard_snapshot(repo_path, &snapshot.id);
+        return Err(err);
+    }
+
+    Ok(snapshot)
+}
+
+fn is_tracked(repo_path: &Path, file_path: &str) -> Result<bool> {
+    let output = Command::new("git")
+        .args(["ls-files", "--error-unmatch", "--", file_path])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git ls-files")?;
+
+    Ok(output.status.success())
+}
+
+/// Builds a patch containing only the selected lines of `file_diff`.
+///
+/// With `reverse == false` the patch is meant to be applied forward (staging):
+/// unselected additions are dropped and unselected deletions become context.
+/// With `reverse == true` it is meant for `git apply --reverse` (unstaging,
+/// discarding): unselected additions become context and unselected deletions
+/// are dropped, since the target already contains the new side.
+fn build_partial_patch(
+    file_diff: &FileDiff,
+    selection: &[HunkSelection],
+    reverse: bool,
+) -> Result<String> {
+    if file_diff.header.is_empty() || file_diff.hunks.is_empty() {
+        anyhow::bail!("No changes to apply for {}", file_diff.path);
+    }
+
+    let mut body = String::new();
+    let mut complete = true;
+    let mut delta: i64 = 0;
+
+    for (hunk_index, hunk) in file_diff.hunks.iter().enumerate() {
+        let Some(hunk_selection) = selection.iter().find(|s| s.hunk_index == hunk_index) else {
+            if hunk.lines.iter().any(|l| l.kind != DiffLineKind::Context) {
+                complete = false;
+            }
+            continue;
+        };
+
+        let is_selected = |line_index: usize| {
+            hunk_selection
+                .line_indices
+                .as_ref()
+                .is_none_or(|ids| ids.contains(&line_index))
+        };
+
+        let mut lines = String::new();
+        let mut old_count = 0u32;
+        let mut new_count = 0u32;
+        let mut changed = false;
+
+        for (line_index, line) in hunk.lines.iter().enumerate() {
+            let prefix = match (line.kind, is_selected(line_index)) {
+                (DiffLineKind::Context, _) => ' ',
+                (DiffLineKind::Addition, true) | (DiffLineKind::Deletion, true) => {
+                    changed = true;
+                    if line.kind == DiffLineKind::Addition {
+                        '+'
+                    } else {
+                        '-'
+                    }
+                }
+                (DiffLineKind::Addition, false) => {
+                    complete = false;
+                    if reverse {
+                        ' '
+                    } else {
+                        continue;
+                    }
+                }
+                (DiffLineKind::Deletion, false) => {
+                    complete = false;
+                    if reverse {
+                        continue;
+                    } else {
+                        ' '
+                    }
+                }
+            };
+
+            match prefix {
+                '+' => new_count += 1,
+                '-' => old_count += 1,
+                _ => {
+                    old_count += 1;
+                    new_count += 1;
+                }
+            }
+
+            lines.push(prefix);
+            lines.push_str(&line.content);
+            lines.push('\n');
+            if line.no_newline {
+                lines.push_str("\\ No newline at end of file\n");
+            }
+        }
+
+        if !changed {
+            continue;
+        }
+
+        // Keep the side that exists in the target as the anchor and shift the other one
+        let (old_start, new_start) = if reverse {
+            let first_line = hunk_first_line(hunk.new_start, hunk.new_lines);
+            (
+                hunk_range_start(first_line - delta, old_count),
+                i64::from(hunk.new_start),
+            )
+        } else {
+            let first_line = hunk_first_line(hunk.old_start, hunk.old_lines);
+            (
+                i64::from(hunk.old_start),
+                hunk_range_start(first_line + delta, new_count),
+            )
+        };
+        delta += i64::from(new_count) - i64::from(old_count);
+
+        body.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+        body.push_str(&lines);
+    }
+
+    if body.is_empty() {
+        anyhow::bail!("No lines selected");
+    }
+
+    let mut patch = String::new();
+    for line in &file_diff.header {
+        // A partial patch cannot create or delete the whole file: rewrite it as a modification
+        if !complete {
+            if line.starts_with("new file mode") || line.starts_with("deleted file mode") {
+                continue;
+            }
+            if line == "--- /dev/null" {
+                patch.push_str(&format!("--- a/{}\n", file_diff.path));
+                continue;
+            }
+            if line == "+++ /dev/null" {
+                patch.push_str(&format!("+++ b/{}\n", file_diff.path));
+                continue;
+            }
+        }
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    patch.push_str(&body);
+
+    Ok(patch)
+}
+
+/// Empty ranges in unified diffs point at the line before the change.
+fn hunk_first_line(start: u32, count: u32) -> i64 {
+    if count == 0 {
+        i64::from(start) + 1
+    } else {
+        i64::from(start)
+    }
+}
+
+fn hunk_range_start(first_line: i64, count: u32) -> i64 {
+    if count == 0 {
+        (first_line - 1).max(0)
+    } else {
+        first_line.max(1)
+    }
+}
+
+fn apply_patch(repo_path: &Path, patch: &str, extra_args: &[&str]) -> Result<()> {
+    let mut child = Command::new("git")
+        .arg("apply")
+        .args(extra_args)
+        .args(["--whitespace=nowarn", "-"])
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git apply")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(patch.as_bytes())
+            .context("Failed to write patch to git apply")?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for git apply")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git apply failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+pub fn commit(repo_path: &PathBuf, message: &str) -> Result<()> {
+    let output = git_command()
+        .args(&["commit", "-m", message])
+        .current_dir(repo_path)
+        .output()
         .context("Failed to execute git commit")?;
 
     if !output.status.success() {
@@ -472,93 +1891,589 @@ pub fn is_last_commit_pushed(repo_path: &PathBuf) -> Result<bool> {
     Ok(count == 0)
 }
 
-pub fn push(repo_path: &PathBuf) -> Result<String> {
-    let output = git_command()
-        .args(&["push"])
-        .current_dir(repo_path)
-        .output()
-        .context("Failed to execute git push")?;
+// ============================================================================
+// Progress and Cancellation
+// ============================================================================
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if stderr.contains("has no upstream branch") {
-            let branch_output = Command::new("git")
-                .args(&["rev-parse", "--abbrev-ref", "HEAD"])
-                .current_dir(repo_path)
-                .output()
-                .context("Failed to detect current branch for upstream push")?;
+/// Progress reporting and cancellation for long-running network commands.
+pub struct OperationControl {
+    cancelled: Arc<AtomicBool>,
+    on_progress: Box<dyn Fn(GitProgress) + Send>,
+}
 
-            if !branch_output.status.success() {
-                anyhow::bail!(
-                    "Failed to detect current branch: {}",
-                    String::from_utf8_lossy(&branch_output.stderr)
-                );
-            }
+impl OperationControl {
+    pub fn new(cancelled: Arc<AtomicBool>, on_progress: Box<dyn Fn(GitProgress) + Send>) -> Self {
+        Self {
+            cancelled,
+            on_progress,
+        }
+    }
 
-            let branch = String::from_utf8_lossy(&branch_output.stdout)
-                .trim()
-                .to_string();
-            if branch.is_empty() || branch == "HEAD" {
-                anyhow::bail!("Failed to detect current branch name");
-            }
+    /// For synchronous callers: never cancelled, progress is dropped.
+    pub fn none() -> Self {
+        Self::new(Arc::new(AtomicBool::new(false)), Box::new(|_| {}))
+    }
 
-            let push_output = git_command()
-                .current_dir(repo_path)
-                .args(&["push", "--set-upstream", "origin", &branch])
-                .output()
-                .context("Failed to push new upstream branch")?;
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
 
-            if !push_output.status.success() {
-                anyhow::bail!(
-                    "Git push failed: {}",
-                    String::from_utf8_lossy(&push_output.stderr)
-                );
+/// Runs a git command that was given `--progress`, reporting parsed progress lines and
+/// killing the process if the operation is cancelled. Progress lines are left out of
+/// the returned stderr.
+fn run_with_progress(mut command: Command, control: &OperationControl) -> Result<Output> {
+    // Own process group, so cancelling also stops helpers like ssh or git-remote-https
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start git")?;
+
+    let mut stdout = child
+        .stdout
+        .take()
+        .context("Failed to capture git output")?;
+    let stderr = BufReader::new(
+        child
+            .stderr
+            .take()
+            .context("Failed to capture git output")?,
+    );
+
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stdout.read_to_end(&mut buffer);
+        buffer
+    });
+
+    let (tx, rx) = mpsc::channel();
+    let stderr_reader = std::thread::spawn(move || {
+        let mut messages = Vec::new();
+        for_each_progress_line(stderr, |line| {
+            let text = String::from_utf8_lossy(line);
+            match parse_progress_line(&text) {
+                Some(progress) => {
+                    let _ = tx.send(progress);
+                }
+                None if !text.trim().is_empty() => {
+                    messages.extend_from_slice(line);
+                    messages.push(b'\n');
+                }
+                None => {}
+            }
+        });
+        messages
+    });
+
+    let status = loop {
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(progress) => (control.on_progress)(progress),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // stderr closed but the process may still be exiting; avoid spinning
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                std::thread::sleep(Duration::from_millis(50))
             }
+        }
 
-            return Ok(String::from_utf8_lossy(&push_output.stdout).to_string());
+        if control.is_cancelled() {
+            kill_process_tree(&mut child);
+            // The readers finish on their own once every holder of the pipes has exited
+            anyhow::bail!("Operation cancelled");
         }
 
-        anyhow::bail!("Git push failed: {}", stderr);
+        if let Some(status) = child.try_wait().context("Failed to wait for git")? {
+            break status;
+        }
+    };
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+    for progress in rx.try_iter() {
+        (control.on_progress)(progress);
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
 }
 
-pub fn pull(repo_path: &PathBuf) -> Result<String> {
-    let output = git_command()
-        .args(&["pull"])
-        .current_dir(repo_path)
-        .output()
-        .context("Failed to execute git pull")?;
+/// Calls `on_line` for every line read from git's stderr. Progress is redrawn with \r, so
+/// both line endings end a line.
+fn for_each_progress_line(mut reader: impl BufRead, mut on_line: impl FnMut(&[u8])) {
+    let mut line = Vec::new();
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        for &byte in chunk {
+            if byte == b'\r' || byte == b'\n' {
+                on_line(&line);
+                line.clear();
+            } else {
+                line.push(byte);
+            }
+        }
+        let consumed = chunk.len();
+        reader.consume(consumed);
+    }
+    on_line(&line);
+}
+
+fn kill_process_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    let _ = Command::new("kill")
+        .args(["-KILL", &format!("-{}", child.id())])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    #[cfg(windows)]
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn parse_progress_line(line: &str) -> Option<GitProgress> {
+    let line = line.trim();
+    let (remote, line) = match line.strip_prefix("remote:") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, line),
+    };
+
+    let (phase, rest) = line.split_once(": ")?;
+    if phase.is_empty() || !phase.chars().all(|c| c.is_ascii_alphabetic() || c == ' ') {
+        return None;
+    }
+    let rest = rest.trim();
+    let done = rest.ends_with("done.") || rest.ends_with("done");
+
+    let mut progress = GitProgress {
+        phase: phase.to_string(),
+        remote,
+        percent: None,
+        current: None,
+        total: None,
+        throughput: None,
+        done,
+    };
+
+    if let Some((percent, after)) = rest.split_once('%') {
+        // "45% (9/20), 1.2 MiB | 800 KiB/s"
+        progress.percent = Some(percent.trim().parse().ok()?);
+        if let Some((counts, after_counts)) = after
+            .trim()
+            .strip_prefix('(')
+            .and_then(|a| a.split_once(')'))
+        {
+            if let Some((current, total)) = counts.split_once('/') {
+                progress.current = current.trim().parse().ok();
+                progress.total = total.trim().parse().ok();
+            }
+            let throughput = after_counts
+                .trim_start_matches(',')
+                .trim()
+                .trim_end_matches("done.")
+                .trim()
+                .trim_end_matches(',')
+                .trim();
+            if !throughput.is_empty() {
+                progress.throughput = Some(throughput.to_string());
+            }
+        }
+    } else {
+        // "Enumerating objects: 5, done." or "Counting objects: 12"
+        let count = rest.split(',').next()?.trim();
+        progress.current = Some(count.parse().ok()?);
+    }
+
+    Some(progress)
+}
+
+pub fn push(
+    repo_path: &Path,
+    options: &PushOptions,
+    protected_branches: &[String],
+) -> Result<String> {
+    push_with_progress(
+        repo_path,
+        options,
+        protected_branches,
+        &OperationControl::none(),
+    )
+}
+
+/// Pushes according to `options`. Force-pushing a branch matching `protected_branches`
+/// is refused.
+pub fn push_with_progress(
+    repo_path: &Path,
+    options: &PushOptions,
+    protected_branches: &[String],
+    control: &OperationControl,
+) -> Result<String> {
+    let mut command = git_command();
+    command.args(["push", "--progress"]).current_dir(repo_path);
+    if options.dry_run {
+        command.arg("--dry-run");
+    }
+
+    if let Some(tag) = options.tag.as_deref() {
+        let remote = match &options.remote {
+            Some(remote) => remote.clone(),
+            None => default_remote(repo_path, current_branch_name(repo_path).as_deref())?,
+        };
+        // Moving an existing tag on the remote needs a force push. Tags have no
+        // remote-tracking refs, so a bare lease would not protect anything
+        if options.force_with_lease {
+            let expected = non_empty(&options.expected_oid).ok_or_else(|| {
+                anyhow!("Moving a tag on the remote needs the object it points at there")
+            })?;
+            command.arg(format!(
+                "--force-with-lease=refs/tags/{}:{}",
+                tag.trim(),
+                expected
+            ));
+        }
+        command.args([remote, format!("refs/tags/{}", tag.trim())]);
+        return run_push(command, options, control);
+    }
+
+    if options.force_with_lease {
+        let target = push_target_branch(repo_path, options)?;
+        if is_protected_branch(&target, protected_branches) {
+            anyhow::bail!(
+                "'{}' is a protected branch; force-pushing it is not allowed",
+                target
+            );
+        }
+        match non_empty(&options.expected_oid) {
+            Some(expected) => {
+                command.arg(format!(
+                    "--force-with-lease=refs/heads/{}:{}",
+                    target, expected
+                ));
+            }
+            // The app fetches in the background, so the remote-tracking branch alone may
+            // already hold commits nobody has seen; they must also be in the local branch
+            None => {
+                command.args([
+                    format!("--force-with-lease=refs/heads/{}", target),
+                    "--force-if-includes".to_string(),
+                ]);
+            }
+        }
+    }
+
+    let explicit = options.remote.is_some()
+        || options.branch.is_some()
+        || options.remote_branch.is_some()
+        || options.tags;
+    if explicit {
+        let branch = match &options.branch {
+            Some(branch) => branch.clone(),
+            None => current_branch_name(repo_path)
+                .ok_or_else(|| anyhow!("Failed to detect current branch name"))?,
+        };
+        let remote = match &options.remote {
+            Some(remote) => remote.clone(),
+            None => default_remote(repo_path, Some(&branch))?,
+        };
+        if options.tags {
+            command.arg("--tags");
+        }
+        if !options.dry_run && branch_upstream(repo_path, &branch).is_none() {
+            command.arg("--set-upstream");
+        }
+        let refspec = match options.remote_branch.as_deref() {
+            Some(remote_branch) => format!("{}:refs/heads/{}", branch, remote_branch.trim()),
+            None => branch,
+        };
+        command.args([remote, refspec]);
+    }
+
+    match run_push(command, options, control) {
+        // A new branch: push it to the default remote and start tracking it
+        Err(err) if !explicit && err.to_string().contains("has no upstream branch") => {
+            let branch = current_branch_name(repo_path)
+                .ok_or_else(|| anyhow!("Failed to detect current branch name"))?;
+            let options = PushOptions {
+                branch: Some(branch),
+                ..options.clone()
+            };
+            push_with_progress(repo_path, &options, protected_branches, control)
+                .context("Failed to push new upstream branch")
+        }
+        result => result,
+    }
+}
+
+fn run_push(command: Command, options: &PushOptions, control: &OperationControl) -> Result<String> {
+    let output = run_with_progress(command, control).context("Failed to execute git push")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git pull failed: {}",
+            "Git push failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if options.dry_run {
+        // The would-be ref updates are reported on stderr
+        return Ok(format!(
+            "{}{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(stdout)
 }
 
-/// Fetch from remote and prune stale remote-tracking references.
-/// This removes local references to remote branches that no longer exist.
-pub fn fetch_prune(repo_path: &PathBuf) -> Result<String> {
+/// Name of the remote branch a push with `options` would update.
+fn push_target_branch(repo_path: &Path, options: &PushOptions) -> Result<String> {
+    if let Some(remote_branch) = &options.remote_branch {
+        return Ok(remote_branch.trim().to_string());
+    }
+
+    let branch = match &options.branch {
+        Some(branch) => branch.clone(),
+        None => current_branch_name(repo_path)
+            .ok_or_else(|| anyhow!("Failed to detect current branch name"))?,
+    };
+    match branch_upstream(repo_path, &branch) {
+        Some((remote, merge)) if options.remote.as_deref().is_none_or(|r| r == remote) => Ok(merge),
+        _ => Ok(branch),
+    }
+}
+
+/// Whether `branch` matches one of the protected branch patterns ("main", "release/*").
+pub fn is_protected_branch(branch: &str, protected_branches: &[String]) -> bool {
+    protected_branches
+        .iter()
+        .any(|pattern| wildcard_match(pattern.trim(), branch))
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let Some((first, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut remaining) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let last = parts.pop().unwrap_or("");
+    for part in parts {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= last.len() && remaining.ends_with(last)
+}
+
+pub fn pull(repo_path: &Path, options: &PullOptions) -> Result<PullResult> {
+    pull_with_progress(repo_path, options, &OperationControl::none())
+}
+
+/// Pulls with an explicit strategy, so the user's `pull.rebase` / `pull.ff` settings never
+/// decide how history is combined.
+pub fn pull_with_progress(
+    repo_path: &Path,
+    options: &PullOptions,
+    control: &OperationControl,
+) -> Result<PullResult> {
+    let strategy = options.strategy.unwrap_or_default();
+    let mut command = git_command();
+    command.args(["pull", "--progress"]).current_dir(repo_path);
+    match strategy {
+        // --ff overrides a configured pull.ff=only
+        PullStrategy::Merge => command.args(["--no-rebase", "--ff"]),
+        PullStrategy::Rebase => command.arg("--rebase"),
+        PullStrategy::FfOnly => command.args(["--no-rebase", "--ff-only"]),
+    };
+    command.arg(if options.autostash {
+        "--autostash"
+    } else {
+        "--no-autostash"
+    });
+
+    // Remote-tracking branch the pull fetches into
+    let mut pulled_ref = "@{upstream}".to_string();
+    if options.remote.is_some() || options.branch.is_some() {
+        let current = current_branch_name(repo_path);
+        let upstream = current
+            .as_deref()
+            .and_then(|b| branch_upstream(repo_path, b));
+        let remote = match (&options.remote, &upstream) {
+            (Some(remote), _) => remote.clone(),
+            (None, Some((remote, _))) => remote.clone(),
+            (None, None) => default_remote(repo_path, current.as_deref())?,
+        };
+        // Without a branch, pull the upstream branch if it lives on that remote, else the same name
+        let branch = match (&options.branch, &upstream, &current) {
+            (Some(branch), _, _) => branch.clone(),
+            (None, Some((upstream_remote, merge)), _) if *upstream_remote == remote => {
+                merge.clone()
+            }
+            (None, _, Some(current)) => current.clone(),
+            (None, _, None) => anyhow::bail!("Choose a branch to pull while HEAD is detached"),
+        };
+        command.args([&remote, &branch]);
+        pulled_ref = format!("refs/remotes/{}/{}", remote, branch);
+    }
+
+    let head_before = resolve_revision(repo_path, "HEAD");
+    // An autostash that applies cleanly is dropped again, so a new stash means it didn't
+    let stash_before = resolve_revision(repo_path, "refs/stash");
+    let output = run_with_progress(command, control).context("Failed to execute git pull")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let mut summary = if stdout.is_empty() {
+        stderr.clone()
+    } else {
+        stdout
+    };
+
+    if !output.status.success() {
+        if let Some(operation) = get_operation_in_progress(repo_path)? {
+            return Ok(PullResult {
+                outcome: PullOutcome::Conflicts,
+                strategy,
+                summary,
+                operation: Some(operation),
+                autostash_pending: false,
+            });
+        }
+        // Told apart by history rather than by git's (localized) messages
+        let diverged = strategy == PullStrategy::FfOnly
+            && count_ahead_behind(repo_path, "HEAD", &pulled_ref)
+                .is_ok_and(|(ahead, behind)| ahead > 0 && behind > 0);
+        if diverged {
+            return Ok(PullResult {
+                outcome: PullOutcome::Diverged,
+                strategy,
+                summary: "Local and remote branches have diverged; pull with merge or rebase to integrate them".to_string(),
+                operation: None,
+                autostash_pending: false,
+            });
+        }
+        anyhow::bail!("Git pull failed: {}", stderr);
+    }
+
+    let head_after = resolve_revision(repo_path, "HEAD");
+    let outcome = if head_after == head_before {
+        PullOutcome::UpToDate
+    } else if strategy == PullStrategy::Rebase {
+        PullOutcome::Rebased
+    } else if head_before.is_some()
+        && resolve_revision(repo_path, "HEAD^2").is_some()
+        && resolve_revision(repo_path, "HEAD^1") == head_before
+    {
+        PullOutcome::Merged
+    } else {
+        PullOutcome::FastForward
+    };
+
+    let autostash_pending =
+        options.autostash && resolve_revision(repo_path, "refs/stash") != stash_before;
+    if autostash_pending && !stderr.is_empty() && !summary.contains(&stderr) {
+        summary = format!("{}\n{}", summary, stderr);
+    }
+
+    Ok(PullResult {
+        outcome,
+        strategy,
+        summary,
+        operation: None,
+        autostash_pending,
+    })
+}
+
+pub fn resolve_revision(repo_path: &Path, revision: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", revision])
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+
+    let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !hash.is_empty() {
+        Some(hash)
+    } else {
+        None
+    }
+}
+
+/// Commits only in `left` and only in `right`.
+fn count_ahead_behind(repo_path: &Path, left: &str, right: &str) -> Result<(u32, u32)> {
     let output = Command::new("git")
-        .args(&["fetch", "--prune", "origin"])
+        .args([
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("{left}...{right}"),
+            "--",
+        ])
         .current_dir(repo_path)
         .output()
-        .context("Failed to execute git fetch --prune")?;
+        .context("Failed to execute git rev-list")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git rev-list failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let counts = String::from_utf8_lossy(&output.stdout);
+    let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+    Ok((counts.next().unwrap_or(0), counts.next().unwrap_or(0)))
+}
+
+/// Fetch from a remote (or every remote when `remote` is None) and prune stale
+/// remote-tracking references.
+/// This removes local references to remote branches that no longer exist.
+pub fn fetch_prune(repo_path: &Path, remote: Option<&str>) -> Result<String> {
+    fetch_prune_with_progress(repo_path, remote, &OperationControl::none())
+}
+
+pub fn fetch_prune_with_progress(
+    repo_path: &Path,
+    remote: Option<&str>,
+    control: &OperationControl,
+) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .args(["fetch", "--progress", "--prune"])
+        .arg(remote.unwrap_or("--all"))
+        .current_dir(repo_path);
+    let output =
+        run_with_progress(command, control).context("Failed to execute git fetch --prune")?;
 
     // fetch --prune can succeed even with warnings, so we check both stdout and stderr
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
     if !output.status.success() {
-        // Check if it's just a "no remote" error (repo without origin)
+        // Check if it's just a "no remote" error (repo without that remote)
         if stderr.contains("does not appear to be a git repository")
             || stderr.contains("Could not read from remote")
-            || stderr.contains("'origin' does not appear to be a git repository")
         {
             // No remote configured - not an error, just return empty
             return Ok(String::new());
@@ -570,57 +2485,156 @@ pub fn fetch_prune(repo_path: &PathBuf) -> Result<String> {
     Ok(format!("{}{}", stdout, stderr))
 }
 
-pub fn get_branches(repo_path: &PathBuf) -> Result<Vec<Branch>> {
-    // Get local branches
-    let output = Command::new("git")
-        .args(&["branch", "--list"])
-        .current_dir(repo_path)
-        .output()
-        .context("Failed to execute git branch")?;
+/// Format parsed by `parse_branch_record`; `%(ahead-behind:...)` is appended when supported.
+const BRANCH_FORMAT: &str = "%(refname)%1f%(HEAD)%1f%(symref)%1f%(upstream:short)%1f\
+%(upstream:track,nobracket)%1f%(objectname)%1f%(authorname)%1f%(authordate:iso)%1f%(contents:subject)";
+
+/// Local and remote-tracking branches with their tracking state, position relative to the
+/// default branch and last commit.
+pub fn get_branches(repo_path: &Path) -> Result<Vec<Branch>> {
+    let default_ref = get_default_branch(repo_path);
+
+    // `%(ahead-behind:...)` needs git 2.41; older versions get the counts from rev-list
+    let mut output = for_each_branch(repo_path, default_ref.as_deref())?;
+    let mut counted = default_ref.is_some();
+    if !output.status.success() && default_ref.is_some() {
+        output = for_each_branch(repo_path, None)?;
+        counted = false;
+    }
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git branch failed: {}",
+            "Git for-each-ref failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
     let mut branches = Vec::new();
-    let branch_output = String::from_utf8_lossy(&output.stdout);
-
-    for line in branch_output.lines() {
-        let is_current = line.starts_with("*");
-        let name = line.trim_start_matches("* ").trim().to_string();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((full_name, mut branch)) = parse_branch_record(line) else {
+            continue;
+        };
 
-        branches.push(Branch {
-            name,
-            current: is_current,
-            remote: false,
-        });
+        if let Some(default_ref) = default_ref.as_deref() {
+            branch.is_default = full_name == default_ref;
+            if !counted {
+                let (ahead, behind) = count_ahead_behind(repo_path, &full_name, default_ref)?;
+                branch.default_ahead = Some(ahead);
+                branch.default_behind = Some(behind);
+            }
+            branch.merged = !branch.is_default && branch.default_ahead == Some(0);
+        }
+        branches.push(branch);
     }
 
-    // Get remote branches
-    let output = Command::new("git")
-        .args(&["branch", "-r"])
+    Ok(branches)
+}
+
+fn for_each_branch(repo_path: &Path, default_ref: Option<&str>) -> Result<Output> {
+    let format = match default_ref {
+        Some(default_ref) => format!("--format={BRANCH_FORMAT}%1f%(ahead-behind:{default_ref})"),
+        None => format!("--format={BRANCH_FORMAT}"),
+    };
+
+    Command::new("git")
+        .args(["for-each-ref", &format, "refs/heads", "refs/remotes"])
         .current_dir(repo_path)
         .output()
-        .context("Failed to execute git branch -r")?;
+        .context("Failed to execute git for-each-ref")
+}
 
-    if output.status.success() {
-        let remote_output = String::from_utf8_lossy(&output.stdout);
-        for line in remote_output.lines() {
-            let name = line.trim().to_string();
-            if !name.contains("HEAD") {
-                branches.push(Branch {
-                    name,
-                    current: false,
-                    remote: true,
-                });
-            }
+/// Returns the full ref name alongside the branch, so it can be compared with other refs.
+fn parse_branch_record(line: &str) -> Option<(String, Branch)> {
+    let parts: Vec<&str> = line.splitn(10, '\u{001f}').collect();
+    if parts.len() < 9 {
+        return None;
+    }
+
+    // Skip "origin/HEAD" and other symbolic refs
+    if !parts[2].is_empty() {
+        return None;
+    }
+
+    let full_name = parts[0].to_string();
+    let (name, remote) = if let Some(name) = full_name.strip_prefix("refs/heads/") {
+        (name.to_string(), false)
+    } else {
+        (full_name.strip_prefix("refs/remotes/")?.to_string(), true)
+    };
+
+    // "ahead 1, behind 2", "ahead 1", "gone" or empty when up to date
+    let track = parts[4].trim();
+    let mut ahead = 0;
+    let mut behind = 0;
+    for count in track.split(", ") {
+        if let Some(n) = count.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = count.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
         }
     }
 
-    Ok(branches)
+    // "<ahead> <behind>" relative to the default branch
+    let default_counts = parts.get(9).and_then(|counts| {
+        let (ahead, behind) = counts.trim().split_once(' ')?;
+        Some((ahead.parse().ok()?, behind.parse().ok()?))
+    });
+
+    let upstream = parts[3].trim();
+    let branch = Branch {
+        name,
+        current: parts[1] == "*",
+        remote,
+        upstream: (!upstream.is_empty()).then(|| upstream.to_string()),
+        upstream_gone: track == "gone",
+        ahead,
+        behind,
+        is_default: false,
+        default_ahead: default_counts.map(|(ahead, _)| ahead),
+        default_behind: default_counts.map(|(_, behind)| behind),
+        merged: false,
+        last_commit: Some(BranchCommit {
+            hash: parts[5].to_string(),
+            author: parts[6].to_string(),
+            date: parts[7].to_string(),
+            subject: parts[8].to_string(),
+        }),
+    };
+    Some((full_name, branch))
+}
+
+/// Full ref of the repository's main line: the local branch the default remote's HEAD
+/// points at (or that remote branch itself when there is no local copy), otherwise
+/// `init.defaultBranch`, "main" or "master" when such a local branch exists.
+pub fn get_default_branch(repo_path: &Path) -> Option<String> {
+    if let Ok(remote) = default_remote(repo_path, None) {
+        let output = Command::new("git")
+            .args([
+                "symbolic-ref",
+                "--quiet",
+                &format!("refs/remotes/{remote}/HEAD"),
+            ])
+            .current_dir(repo_path)
+            .output()
+            .ok()?;
+        let remote_head = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        if output.status.success() && !remote_head.is_empty() {
+            let local = remote_head
+                .strip_prefix(&format!("refs/remotes/{remote}/"))
+                .map(|branch| format!("refs/heads/{branch}"));
+            return match local {
+                Some(local) if resolve_revision(repo_path, &local).is_some() => Some(local),
+                _ => Some(remote_head),
+            };
+        }
+    }
+
+    config_value(repo_path, "init.defaultBranch")
+        .into_iter()
+        .chain(["main".to_string(), "master".to_string()])
+        .map(|branch| format!("refs/heads/{branch}"))
+        .find(|branch| resolve_revision(repo_path, branch).is_some())
 }
 
 pub fn checkout_branch(repo_path: &PathBuf, branch_name: &str) -> Result<()> {
@@ -640,1270 +2654,4777 @@ pub fn checkout_branch(repo_path: &PathBuf, branch_name: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn get_log(repo_path: &PathBuf, limit: usize) -> Result<Vec<CommitInfo>> {
-    let output = Command::new("git")
-        .args(&[
-            "log",
-            &format!("-{}", limit),
-            "--pretty=format:%H%x1f%B%x1f%an%x1f%ai%x1e",
-        ])
-        .current_dir(repo_path)
-        .output()
-        .context("Failed to execute git log")?;
+/// Pretty format parsed by `parse_commit_log`; the message goes last so it may contain anything.
+const COMMIT_LOG_FORMAT: &str =
+    "--pretty=format:%H%x1f%P%x1f%an%x1f%ae%x1f%ai%x1f%cn%x1f%ce%x1f%ci%x1f%D%x1f%B%x1e";
 
-    if !output.status.success() {
-        anyhow::bail!(
-            "Git log failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
+fn parse_commit_log(log_output: &str) -> Vec<CommitInfo> {
+    log_output
+        .split('\u{001e}')
+        .filter_map(parse_commit_record)
+        .collect()
+}
+
+fn parse_commit_record(record: &str) -> Option<CommitInfo> {
+    if record.trim().is_empty() {
+        return None;
     }
 
-    let log_output = String::from_utf8_lossy(&output.stdout);
-    let mut commits = Vec::new();
+    let parts: Vec<&str> = record.splitn(10, '\u{001f}').collect();
+    if parts.len() < 10 {
+        return None;
+    }
 
-    for record in log_output.split('\u{001e}') {
-        if record.trim().is_empty() {
-            continue;
-        }
+    Some(CommitInfo {
+        hash: parts[0].trim().to_string(),
+        parents: parts[1].split_whitespace().map(str::to_string).collect(),
+        author: parts[2].trim().to_string(),
+        author_email: parts[3].trim().to_string(),
+        date: parts[4].trim().to_string(),
+        committer: parts[5].trim().to_string(),
+        committer_email: parts[6].trim().to_string(),
+        committer_date: parts[7].trim().to_string(),
+        refs: parse_decorations(parts[8].trim()),
+        message: parts[9].to_string(),
+    })
+}
 
-        let parts: Vec<&str> = record.split('\u{001f}').collect();
-        if parts.len() < 4 {
-            continue;
+/// Parses `%D` output produced with `--decorate=full`.
+fn parse_decorations(decorations: &str) -> Vec<CommitRef> {
+    let mut refs = Vec::new();
+
+    for decoration in decorations.split(", ").filter(|d| !d.is_empty()) {
+        if let Some(branch) = decoration.strip_prefix("HEAD -> ") {
+            let mut commit_ref = commit_ref_from_full_name(branch);
+            commit_ref.is_head = true;
+            refs.push(commit_ref);
+        } else if decoration == "HEAD" {
+            refs.push(CommitRef {
+                name: "HEAD".to_string(),
+                full_name: "HEAD".to_string(),
+                kind: CommitRefKind::Head,
+                is_head: true,
+            });
+        } else if let Some(tag) = decoration.strip_prefix("tag: ") {
+            refs.push(commit_ref_from_full_name(tag));
+        } else {
+            refs.push(commit_ref_from_full_name(decoration));
         }
-
-        commits.push(CommitInfo {
-            hash: parts[0].trim().to_string(),
-            message: parts[1].to_string(),
-            author: parts[2].trim().to_string(),
-            date: parts[3].trim().to_string(),
-        });
     }
 
-    Ok(commits)
+    refs
 }
 
-pub fn get_remote_origin_url(repo_path: &PathBuf) -> Result<Option<String>> {
-    let output = Command::new("git")
-        .args(&["remote", "get-url", "origin"])
-        .current_dir(repo_path)
-        .output()
-        .context("Failed to execute git remote get-url origin")?;
-
-    if !output.status.success() {
-        return Ok(None);
-    }
-
-    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if url.is_empty() {
-        Ok(None)
+fn commit_ref_from_full_name(full_name: &str) -> CommitRef {
+    let (kind, name) = if let Some(name) = full_name.strip_prefix("refs/heads/") {
+        (CommitRefKind::Branch, name)
+    } else if let Some(name) = full_name.strip_prefix("refs/remotes/") {
+        (CommitRefKind::RemoteBranch, name)
+    } else if let Some(name) = full_name.strip_prefix("refs/tags/") {
+        (CommitRefKind::Tag, name)
     } else {
-        Ok(Some(url))
+        (
+            CommitRefKind::Other,
+            full_name.strip_prefix("refs/").unwrap_or(full_name),
+        )
+    };
+
+    CommitRef {
+        name: name.to_string(),
+        full_name: full_name.to_string(),
+        kind,
+        is_head: false,
     }
 }
 
-pub fn get_commit_shortstat(repo_path: &PathBuf, hash: &str) -> Result<CommitShortStat> {
-    let hash = hash.trim();
-    let output = Command::new("git")
-        .args(&["show", "--shortstat", "--format=", "--no-color", hash])
-        .current_dir(repo_path)
-        .output()
-        .context("Failed to execute git show --shortstat")?;
+/// One page of history matching `query`, children always before their parents.
+///
+/// Each page resumes the walk where the previous one stopped instead of re-walking the
+/// earlier pages. The walk position is kept here and the cursor only names it, so commits
+/// created while paging don't shift later pages and `all_refs` doesn't carry every ref
+/// hash back and forth.
+pub fn get_log(repo_path: &Path, limit: usize, query: &LogQuery) -> Result<LogPage> {
+    let limit = limit.max(1);
+    let revisions = match query.cursor.as_deref() {
+        Some(cursor) => load_log_cursor(cursor)?,
+        None => resolve_log_revisions(repo_path, query)?,
+    };
 
-    if !output.status.success() {
-        anyhow::bail!(
-            "Git show failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
+    if revisions.is_empty() {
+        // Unborn branch: nothing to list yet
+        return Ok(LogPage {
+            commits: Vec::new(),
+            next_cursor: None,
+        });
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut shortstat = CommitShortStat {
-        files_changed: None,
-        insertions: None,
-        deletions: None,
-    };
+    // Options that decide which commits are walked (and in what order), as opposed to
+    // the filters below that only hide some of them
+    let mut walk_args = vec!["--date-order".to_string()];
+    if let Some(since) = non_empty(&query.since) {
+        walk_args.push(format!("--since={since}"));
+    }
+    if let Some(until) = non_empty(&query.until) {
+        walk_args.push(format!("--until={until}"));
+    }
+    let paths: Vec<String> = query
+        .paths
+        .iter()
+        .filter(|p| !p.is_empty())
+        .cloned()
+        .collect();
 
-    for line in stdout.lines() {
-        let trimmed = line.trim();
-        if !(trimmed.contains("file changed") || trimmed.contains("files changed")) {
-            continue;
+    let mut args = vec![
+        "log".to_string(),
+        "--decorate=full".to_string(),
+        COMMIT_LOG_FORMAT.to_string(),
+        // One extra commit tells whether there is a next page
+        format!("--max-count={}", limit + 1),
+    ];
+    args.extend(walk_args.iter().cloned());
+    if let Some(author) = non_empty(&query.author) {
+        args.push(format!("--author={author}"));
+    }
+    if let Some(grep) = non_empty(&query.grep) {
+        args.push(format!("--grep={grep}"));
+    }
+    if query.ignore_case {
+        args.push("--regexp-ignore-case".to_string());
+    }
+    if let Some(pickaxe) = non_empty(&query.pickaxe) {
+        match query.pickaxe_mode {
+            PickaxeMode::Text => args.push(format!("-S{pickaxe}")),
+            PickaxeMode::Regex => args.push(format!("-G{pickaxe}")),
         }
+    }
+    // Revisions go through stdin: with `all_refs` they can be thousands of hashes, more
+    // than fits on a command line
+    args.push("--stdin".to_string());
+    args.push("--".to_string());
+    args.extend(paths.iter().cloned());
 
-        for part in trimmed.split(',') {
-            let part = part.trim();
-            let Some(first_token) = part.split_whitespace().next() else {
-                continue;
-            };
+    let output = run_log_with_revisions(repo_path, &args, &revisions)?;
 
-            let Ok(value) = first_token.parse::<u32>() else {
-                continue;
-            };
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git log failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
 
-            if part.contains("file changed") || part.contains("files changed") {
-                shortstat.files_changed = Some(value);
-            } else if part.contains("insertion") {
-                shortstat.insertions = Some(value);
-            } else if part.contains("deletion") {
-                shortstat.deletions = Some(value);
-            }
+    let mut commits = parse_commit_log(&String::from_utf8_lossy(&output.stdout));
+    let next_cursor = if commits.len() > limit {
+        commits.truncate(limit);
+        let last = &commits[limit - 1].hash;
+        let frontier = log_frontier(repo_path, &walk_args, &paths, &revisions, last)?;
+        Some(store_log_cursor(frontier))
+    } else {
+        None
+    };
+
+    Ok(LogPage {
+        commits,
+        next_cursor,
+    })
+}
+
+/// Where the walk from `revisions` continues after `last`: the starting points and the
+/// parents of every walked commit that weren't walked yet, plus the `^` exclusions.
+///
+/// The filters of the query don't change the walk, so this replays it without them up
+/// to `last`. With `--date-order` no commit comes before its children, so nothing
+/// reachable from the frontier was listed already.
+fn log_frontier(
+    repo_path: &Path,
+    walk_args: &[String],
+    paths: &[String],
+    revisions: &[String],
+    last: &str,
+) -> Result<Vec<String>> {
+    // `--sparse` also lists the walked commits that don't touch `paths`
+    let mut args = vec![
+        "rev-list".to_string(),
+        "--parents".to_string(),
+        "--sparse".to_string(),
+    ];
+    args.extend(walk_args.iter().cloned());
+    args.push("--stdin".to_string());
+    args.push("--".to_string());
+    args.extend(paths.iter().cloned());
+
+    let mut child = spawn_with_revisions(repo_path, &args, revisions)?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to read git rev-list output"))?;
+
+    let mut walked = HashSet::new();
+    let mut parents = Vec::new();
+    let mut found = false;
+    for line in BufReader::new(stdout).lines() {
+        let line = line.context("Failed to read git rev-list output")?;
+        let mut hashes = line.split_whitespace();
+        let Some(hash) = hashes.next() else {
+            continue;
+        };
+        walked.insert(hash.to_string());
+        parents.extend(hashes.map(str::to_string));
+        if hash == last {
+            found = true;
+            break;
         }
+    }
+    // The rest of the history isn't needed
+    let _ = child.kill();
+    let _ = child.wait();
 
-        break;
+    if !found {
+        anyhow::bail!("Failed to find where the log page ended");
     }
 
-    Ok(shortstat)
+    let (excluded, starts): (Vec<&String>, Vec<&String>) =
+        revisions.iter().partition(|rev| rev.starts_with('^'));
+    let mut seen = HashSet::new();
+    Ok(starts
+        .into_iter()
+        .chain(parents.iter())
+        .filter(|hash| !walked.contains(*hash) && seen.insert(hash.as_str()))
+        .chain(excluded)
+        .cloned()
+        .collect())
 }
 
-/// Cria branch local a partir de remota com tracking e faz checkout
-/// remote_ref: "origin/feature/x" → cria "feature/x" com upstream
-pub fn checkout_remote_branch(repo_path: &PathBuf, remote_ref: &str) -> Result<()> {
-    // Deriva nome local: "origin/feature/x" → "feature/x"
-    let local_name = match remote_ref.find('/') {
-        Some(pos) => &remote_ref[pos + 1..],
-        None => remote_ref,
-    };
+/// Walk positions of paged logs by cursor id. Only the most recent ones are kept; a log
+/// view scrolled past that simply reloads from the top.
+static LOG_CURSORS: Mutex<Vec<(usize, Vec<String>)>> = Mutex::new(Vec::new());
+static NEXT_LOG_CURSOR: AtomicUsize = AtomicUsize::new(1);
+const MAX_LOG_CURSORS: usize = 64;
+
+fn store_log_cursor(revisions: Vec<String>) -> String {
+    let id = NEXT_LOG_CURSOR.fetch_add(1, Ordering::Relaxed);
+    let mut cursors = LOG_CURSORS.lock().unwrap_or_else(|err| err.into_inner());
+    if cursors.len() >= MAX_LOG_CURSORS {
+        cursors.remove(0);
+    }
+    cursors.push((id, revisions));
+    id.to_string()
+}
 
-    // Cria branch local com tracking e faz checkout
-    // Nota: UI garante que branch local não existe (filtro no dropdown)
-    let output = git_command()
-        .args(&["checkout", "-b", local_name, "--track", remote_ref])
+fn load_log_cursor(cursor: &str) -> Result<Vec<String>> {
+    let id: usize = cursor.parse().map_err(|_| anyhow!("Invalid log cursor"))?;
+    LOG_CURSORS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .find(|(cursor_id, _)| *cursor_id == id)
+        .map(|(_, revisions)| revisions.clone())
+        .ok_or_else(|| anyhow!("The log cursor expired; reload the log"))
+}
+
+/// Spawns git with `args` (which must include `--stdin`), writing `revisions` to stdin.
+fn spawn_with_revisions(
+    repo_path: &Path,
+    args: &[String],
+    revisions: &[String],
+) -> Result<std::process::Child> {
+    let command = args.first().map(String::as_str).unwrap_or("git");
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to execute git {command}"))?;
+
+    // git reads all of stdin before it starts writing output; dropping it ends the list
+    if let Some(mut stdin) = child.stdin.take() {
+        for revision in revisions {
+            stdin
+                .write_all(revision.as_bytes())
+                .and_then(|_| stdin.write_all(b"\n"))
+                .with_context(|| format!("Failed to write revisions to git {command}"))?;
+        }
+    }
+
+    Ok(child)
+}
+
+/// Runs `git log` with `args` (which must include `--stdin`), writing `revisions` to stdin.
+fn run_log_with_revisions(
+    repo_path: &Path,
+    args: &[String],
+    revisions: &[String],
+) -> Result<Output> {
+    spawn_with_revisions(repo_path, args, revisions)?
+        .wait_with_output()
+        .context("Failed to wait for git log")
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Resolves the query's revision (or HEAD / all refs) to hashes, keeping `^` exclusions.
+fn resolve_log_revisions(repo_path: &Path, query: &LogQuery) -> Result<Vec<String>> {
+    let mut args = vec!["rev-parse", "--revs-only"];
+    match non_empty(&query.revision) {
+        _ if query.all_refs => args.push("--all"),
+        Some(revision) => args.extend(["--end-of-options", revision]),
+        None => {
+            if !has_head_commit(repo_path) {
+                return Ok(Vec::new());
+            }
+            args.push("HEAD");
+        }
+    }
+
+    let output = Command::new("git")
+        .args(&args)
         .current_dir(repo_path)
         .output()
-        .context("Failed to create and checkout branch")?;
+        .context("Failed to resolve log revision")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git checkout -b failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+            "Invalid revision: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
         );
     }
 
-    Ok(())
+    let mut seen = HashSet::new();
+    let revisions: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && seen.insert(line.to_string()))
+        .map(str::to_string)
+        .collect();
+
+    match non_empty(&query.revision) {
+        Some(revision) if revisions.is_empty() && !query.all_refs => {
+            anyhow::bail!("Unknown revision: {revision}")
+        }
+        _ => Ok(revisions),
+    }
 }
 
-pub fn create_branch(
-    repo_path: &Path,
-    name: &str,
-    from: Option<&str>,
-    push_to_remote: bool,
-    checkout: bool,
-) -> Result<()> {
-    if checkout {
-        // Original behavior: create and checkout
-        let mut cmd = Command::new("git");
-        cmd.current_dir(repo_path)
-            .arg("checkout")
-            .arg("-b")
-            .arg(name);
-        if let Some(base) = from {
-            cmd.arg(base);
-        }
+fn has_head_commit(repo_path: &Path) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
 
-        let output = cmd
-            .output()
-            .context("Failed to execute git checkout -b for new branch")?;
+// ============================================================================
+// File History and Blame
+// ============================================================================
 
-        if !output.status.success() {
-            anyhow::bail!(
-                "Git checkout -b failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
+/// Where the next page of a file history starts.
+///
+/// `--skip` miscounts with `--follow`, so instead of skipping, the next page resumes the
+/// walk right after the last commit shown, following the file under the name it had there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileHistoryCursor {
+    /// Where the walk continues after the last commit shown
+    revisions: Vec<String>,
+    /// Path of the file at that point
+    path: String,
+}
+
+/// Commits that touched a file, following it across renames.
+/// Pass `next_cursor` back as `cursor` to load the next page.
+pub fn get_file_history(
+    repo_path: &Path,
+    file_path: &str,
+    limit: usize,
+    revision: Option<&str>,
+    cursor: Option<&str>,
+) -> Result<FileHistoryPage> {
+    let limit = limit.max(1);
+    let cursor = match cursor {
+        Some(cursor) => {
+            serde_json::from_str(cursor).map_err(|_| anyhow!("Invalid file history cursor"))?
         }
-    } else {
-        // New behavior: create branch without switching
-        let mut cmd = Command::new("git");
-        cmd.current_dir(repo_path).arg("branch").arg(name);
-        if let Some(base) = from {
-            cmd.arg(base);
+        None => {
+            let query = LogQuery {
+                revision: revision.map(str::to_string),
+                ..LogQuery::default()
+            };
+            FileHistoryCursor {
+                revisions: resolve_log_revisions(repo_path, &query)?,
+                path: file_path.to_string(),
+            }
         }
+    };
 
-        let output = cmd
-            .output()
-            .context("Failed to execute git branch for new branch")?;
-
-        if !output.status.success() {
-            anyhow::bail!(
-                "Git branch failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
-        }
+    if cursor.revisions.is_empty() {
+        return Ok(FileHistoryPage {
+            entries: Vec::new(),
+            next_cursor: None,
+        });
     }
 
-    if push_to_remote {
-        let push_output = Command::new("git")
-            .current_dir(repo_path)
-            .args(&["push", "-u", "origin", name])
-            .output()
-            .context("Failed to push new branch")?;
+    // Each commit starts with \x1d; its -z name-status entries follow the \x1e
+    let args = vec![
+        "log".to_string(),
+        "--follow".to_string(),
+        "-M".to_string(),
+        "--name-status".to_string(),
+        "-z".to_string(),
+        "--decorate=full".to_string(),
+        COMMIT_LOG_FORMAT.replacen("format:", "format:%x1d", 1),
+        "--date-order".to_string(),
+        // One extra commit tells whether there is a next page
+        format!("--max-count={}", limit + 1),
+        "--stdin".to_string(),
+        "--".to_string(),
+        cursor.path.clone(),
+    ];
 
-        if !push_output.status.success() {
-            anyhow::bail!(
-                "Git push failed: {}",
-                String::from_utf8_lossy(&push_output.stderr)
-            );
-        }
+    let output = run_log_with_revisions(repo_path, &args, &cursor.revisions)?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git log failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 
-    Ok(())
-}
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut entries = Vec::new();
+    let mut current_path = cursor.path.clone();
 
-pub fn delete_branch(repo_path: &Path, name: &str, force: bool, is_remote: bool) -> Result<()> {
-    let name = name.trim();
+    for record in stdout.split('\u{001d}') {
+        let Some((fields, name_status)) = record.split_once('\u{001e}') else {
+            continue;
+        };
+        let Some(commit) = parse_commit_record(fields) else {
+            continue;
+        };
 
-    if is_remote {
-        let (remote, branch_name) = match name.split_once('/') {
-            Some((remote, branch_name)) if !remote.is_empty() && !branch_name.is_empty() => {
-                (remote, branch_name)
+        let mut tokens = name_status
+            .trim_start_matches('\n')
+            .split('\0')
+            .filter(|token| !token.is_empty());
+        let (change, old_path) = match tokens.next() {
+            Some(status) => {
+                let change = file_change_kind(status);
+                let old_path = match change {
+                    Some(FileChangeKind::Renamed) | Some(FileChangeKind::Copied) => {
+                        let old = tokens.next().map(str::to_string);
+                        if let Some(new) = tokens.next() {
+                            current_path = new.to_string();
+                        }
+                        old
+                    }
+                    _ => {
+                        if let Some(path) = tokens.next() {
+                            current_path = path.to_string();
+                        }
+                        None
+                    }
+                };
+                (change, old_path)
             }
-            _ => ("origin", name),
+            // Merge commits list no changes
+            None => (None, None),
         };
 
-        let output = Command::new("git")
-            .current_dir(repo_path)
-            .args(&["push", remote, "--delete", branch_name])
-            .output()
-            .context("Failed to delete remote branch")?;
+        entries.push(FileHistoryEntry {
+            commit,
+            path: current_path.clone(),
+            old_path: old_path.clone(),
+            change,
+        });
 
-        if !output.status.success() {
-            anyhow::bail!(
-                "Failed to delete remote branch: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
+        // Older commits see the file under its previous name
+        if let Some(old_path) = old_path {
+            current_path = old_path;
         }
+    }
 
-        return Ok(());
+    let next_cursor = if entries.len() > limit {
+        entries.truncate(limit);
+        let last = &entries[limit - 1];
+        // `--follow` filters by diff without simplifying history, so its walk is the plain
+        // one and resumes from the same frontier as any log
+        let date_order = ["--date-order".to_string()];
+        let next = FileHistoryCursor {
+            revisions: log_frontier(
+                repo_path,
+                &date_order,
+                &[],
+                &cursor.revisions,
+                &last.commit.hash,
+            )?,
+            path: last.old_path.clone().unwrap_or_else(|| last.path.clone()),
+        };
+        Some(serde_json::to_string(&next)?)
+    } else {
+        None
+    };
+
+    Ok(FileHistoryPage {
+        entries,
+        next_cursor,
+    })
+}
+
+fn file_change_kind(status: &str) -> Option<FileChangeKind> {
+    match status.chars().next()? {
+        'A' => Some(FileChangeKind::Added),
+        'D' => Some(FileChangeKind::Deleted),
+        'R' => Some(FileChangeKind::Renamed),
+        'C' => Some(FileChangeKind::Copied),
+        _ => Some(FileChangeKind::Modified),
     }
+}
+
+/// Line-by-line authorship of a file, in the working tree or at `options.revision`.
+pub fn get_blame(
+    repo_path: &Path,
+    file_path: &str,
+    options: &BlameOptions,
+) -> Result<Vec<BlameLine>> {
+    let mut args = vec!["blame".to_string(), "--porcelain".to_string()];
+    if options.ignore_whitespace {
+        args.push("-w".to_string());
+    }
+    if options.use_ignore_revs_file && repo_path.join(".git-blame-ignore-revs").is_file() {
+        args.push("--ignore-revs-file".to_string());
+        args.push(".git-blame-ignore-revs".to_string());
+    }
+    if let Some(revision) = non_empty(&options.revision) {
+        args.push(revision.to_string());
+    }
+    args.push("--".to_string());
+    args.push(file_path.to_string());
 
-    let flag = if force { "-D" } else { "-d" };
     let output = Command::new("git")
+        .args(&args)
         .current_dir(repo_path)
-        .args(&["branch", flag, name])
         .output()
-        .context("Failed to delete local branch")?;
+        .context("Failed to execute git blame")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Failed to delete local branch: {}",
+            "Git blame failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    Ok(())
+    Ok(parse_blame_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
 }
 
-// ============================================================================
-// Git Worktrees
-// ============================================================================
+#[derive(Default)]
+struct BlameCommit {
+    author: String,
+    author_email: String,
+    author_time: i64,
+    author_tz: String,
+    summary: String,
+    /// Path in this commit; git prints `filename` only with a commit's first group
+    filename: String,
+}
+
+fn parse_blame_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, BlameCommit> = HashMap::new();
+    let mut lines = Vec::new();
+    // (hash, original line, final line) of the line being read
+    let mut current: Option<(String, usize, usize)> = None;
+
+    for line in output.split('\n') {
+        if let Some(content) = line.strip_prefix('\t') {
+            let Some((hash, original_line, line_number)) = current.take() else {
+                continue;
+            };
+            let commit = commits.get(&hash);
+            lines.push(BlameLine {
+                line_number,
+                original_line,
+                original_path: commit.map(|c| c.filename.clone()).unwrap_or_default(),
+                uncommitted: hash.chars().all(|c| c == '0'),
+                author: commit.map(|c| c.author.clone()).unwrap_or_default(),
+                author_email: commit.map(|c| c.author_email.clone()).unwrap_or_default(),
+                date: commit
+                    .map(|c| format_git_timestamp(c.author_time, &c.author_tz))
+                    .unwrap_or_default(),
+                summary: commit.map(|c| c.summary.clone()).unwrap_or_default(),
+                hash,
+                content: content.to_string(),
+            });
+            continue;
+        }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if current.is_none()
+            && matches!(key.len(), 40 | 64)
+            && key.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            let mut numbers = value.split(' ').filter_map(|n| n.parse::<usize>().ok());
+            let original_line = numbers.next().unwrap_or(0);
+            let line_number = numbers.next().unwrap_or(0);
+            commits.entry(key.to_string()).or_default();
+            current = Some((key.to_string(), original_line, line_number));
+            continue;
+        }
+
+        let Some(commit) = current
+            .as_ref()
+            .and_then(|(hash, _, _)| commits.get_mut(hash))
+        else {
+            continue;
+        };
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-mail" => {
+                commit.author_email = value
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            }
+            "author-time" => commit.author_time = value.parse().unwrap_or(0),
+            "author-tz" => commit.author_tz = value.to_string(),
+            "summary" => commit.summary = value.to_string(),
+            "filename" => commit.filename = value.to_string(),
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+/// Formats a unix timestamp and "+hhmm" offset like git's `%ai`.
+fn format_git_timestamp(timestamp: i64, tz: &str) -> String {
+    let sign = if tz.starts_with('-') { -1 } else { 1 };
+    let digits = tz.trim_start_matches(['+', '-']);
+    let hours: i32 = digits.get(0..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+    let minutes: i32 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+    let offset = FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset"));
+
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| {
+            date.with_timezone(&offset)
+                .format("%Y-%m-%d %H:%M:%S %z")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// History with lane layout for drawing a commit graph.
+/// With `all_refs`, commits reachable from every branch and tag are included, not just HEAD.
+pub fn get_commit_graph(repo_path: &Path, limit: usize, all_refs: bool) -> Result<CommitGraph> {
+    let limit_arg = format!("-{}", limit);
+    let mut args = vec![
+        "log",
+        limit_arg.as_str(),
+        "--date-order",
+        "--decorate=full",
+        COMMIT_LOG_FORMAT,
+    ];
+    if all_refs {
+        args.push("--all");
+    }
 
-/// List all worktrees for the repository
-pub fn get_worktrees(repo_path: &PathBuf) -> Result<Vec<Worktree>> {
     let output = Command::new("git")
-        .args(&["worktree", "list", "--porcelain"])
+        .args(&args)
         .current_dir(repo_path)
         .output()
-        .context("Failed to execute git worktree list")?;
+        .context("Failed to execute git log")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git worktree list failed: {}",
+            "Git log failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut worktrees = Vec::new();
-    let mut current_path: Option<String> = None;
-    let mut current_branch: Option<String> = None;
-    let mut is_first = true;
+    Ok(layout_commit_graph(parse_commit_log(
+        &String::from_utf8_lossy(&output.stdout),
+    )))
+}
 
-    for line in stdout.lines() {
-        if line.starts_with("worktree ") {
-            // Save previous worktree if exists
-            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
-                worktrees.push(Worktree {
-                    path,
-                    branch,
-                    is_main: is_first,
-                });
-                is_first = false;
+/// Assigns each commit a column and the edges to the next row.
+/// Commits must be ordered children before parents (`--date-order` / `--topo-order`).
+fn layout_commit_graph(commits: Vec<CommitInfo>) -> CommitGraph {
+    struct Lane {
+        hash: String,
+        color: usize,
+    }
+    struct PendingEdge {
+        from_column: usize,
+        to_column: usize,
+        hash: String,
+        color: usize,
+    }
+
+    // Each lane holds the hash of the commit it is waiting for.
+    let mut lanes: Vec<Option<Lane>> = Vec::new();
+    let mut next_color = 0usize;
+    let mut rows = Vec::with_capacity(commits.len());
+    let mut pending: Vec<Vec<PendingEdge>> = Vec::with_capacity(commits.len());
+    let mut max_columns = 0usize;
+
+    let allocate = |lanes: &mut Vec<Option<Lane>>, hash: &str, color: usize| -> usize {
+        let lane = Some(Lane {
+            hash: hash.to_string(),
+            color,
+        });
+        match lanes.iter().position(Option::is_none) {
+            Some(index) => {
+                lanes[index] = lane;
+                index
             }
-            current_path = Some(line.trim_start_matches("worktree ").to_string());
-            current_branch = None;
-        } else if line.starts_with("branch ") {
-            // Format: "branch refs/heads/branch-name"
-            let branch_ref = line.trim_start_matches("branch ");
-            let branch_name = branch_ref
-                .strip_prefix("refs/heads/")
-                .unwrap_or(branch_ref)
-                .to_string();
-            current_branch = Some(branch_name);
-        } else if line.starts_with("HEAD ") {
-            // Detached HEAD - use abbreviated hash as branch name
-            if current_branch.is_none() {
-                let hash = line.trim_start_matches("HEAD ");
-                current_branch = Some(format!("(detached {})", &hash[..7.min(hash.len())]));
+            None => {
+                lanes.push(lane);
+                lanes.len() - 1
             }
-        } else if line.trim().is_empty() {
-            // End of a worktree entry
-            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
-                worktrees.push(Worktree {
-                    path,
-                    branch,
-                    is_main: is_first,
+        }
+    };
+
+    for commit in commits {
+        let matching: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, lane)| lane.as_ref().is_some_and(|lane| lane.hash == commit.hash))
+            .map(|(index, _)| index)
+            .collect();
+
+        let (column, color) = match matching.first() {
+            Some(&index) => (index, lanes[index].as_ref().map_or(0, |lane| lane.color)),
+            None => {
+                let color = next_color;
+                next_color += 1;
+                (allocate(&mut lanes, &commit.hash, color), color)
+            }
+        };
+        for index in matching.iter().copied().chain(std::iter::once(column)) {
+            lanes[index] = None;
+        }
+
+        let mut edges = Vec::new();
+        for (index, lane) in lanes.iter().enumerate() {
+            if let Some(lane) = lane {
+                edges.push(PendingEdge {
+                    from_column: index,
+                    to_column: index,
+                    hash: lane.hash.clone(),
+                    color: lane.color,
                 });
-                is_first = false;
             }
         }
-    }
 
-    // Handle last worktree if no trailing newline
-    if let (Some(path), Some(branch)) = (current_path, current_branch) {
-        worktrees.push(Worktree {
-            path,
-            branch,
-            is_main: is_first,
+        for (parent_index, parent) in commit.parents.iter().enumerate() {
+            let existing = lanes
+                .iter()
+                .position(|lane| lane.as_ref().is_some_and(|lane| &lane.hash == parent));
+            let (target, edge_color) = match existing {
+                // Another branch already leads to this parent; join it
+                Some(index) => (
+                    index,
+                    lanes[index].as_ref().map_or(color, |lane| lane.color),
+                ),
+                None if parent_index == 0 => {
+                    lanes[column] = Some(Lane {
+                        hash: parent.clone(),
+                        color,
+                    });
+                    (column, color)
+                }
+                None => {
+                    let parent_color = next_color;
+                    next_color += 1;
+                    (allocate(&mut lanes, parent, parent_color), parent_color)
+                }
+            };
+            edges.push(PendingEdge {
+                from_column: column,
+                to_column: target,
+                hash: parent.clone(),
+                color: edge_color,
+            });
+        }
+
+        while matches!(lanes.last(), Some(None)) {
+            lanes.pop();
+        }
+        max_columns = max_columns.max(column + 1).max(
+            edges
+                .iter()
+                .map(|edge| edge.to_column + 1)
+                .max()
+                .unwrap_or(0),
+        );
+
+        rows.push(GraphRow {
+            commit,
+            column,
+            color,
+            edges: Vec::new(),
         });
+        pending.push(edges);
     }
 
-    Ok(worktrees)
+    // Lanes waiting for the next commit bend into its column instead of running straight down.
+    for index in 0..rows.len() {
+        let next = rows
+            .get(index + 1)
+            .map(|row| (row.commit.hash.clone(), row.column));
+        rows[index].edges = pending[index]
+            .iter()
+            .map(|edge| GraphEdge {
+                from_column: edge.from_column,
+                to_column: match &next {
+                    Some((hash, column)) if *hash == edge.hash => *column,
+                    _ => edge.to_column,
+                },
+                color: edge.color,
+            })
+            .collect();
+    }
+
+    CommitGraph { rows, max_columns }
 }
 
-/// Remove a worktree
-pub fn remove_worktree(repo_path: &PathBuf, worktree_path: &str, force: bool) -> Result<()> {
-    let mut args = vec!["worktree", "remove"];
-    if force {
-        args.push("--force");
-    }
-    args.push(worktree_path);
+pub fn get_remote_origin_url(repo_path: &Path) -> Result<Option<String>> {
+    get_remote_url(repo_path, "origin")
+}
+
+// ============================================================================
+// Remotes
+// ============================================================================
 
+pub fn get_remote_url(repo_path: &Path, name: &str) -> Result<Option<String>> {
     let output = Command::new("git")
-        .args(&args)
+        .args(["remote", "get-url", name])
         .current_dir(repo_path)
         .output()
-        .context("Failed to execute git worktree remove")?;
+        .context("Failed to execute git remote get-url")?;
 
     if !output.status.success() {
-        anyhow::bail!(
-            "Git worktree remove failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
+        return Ok(None);
     }
 
-    Ok(())
-}
-
-/// Open a path in Finder (macOS)
-pub fn open_path_in_finder(path: &str) -> Result<()> {
-    Command::new("open")
-        .arg(path)
-        .output()
-        .context("Failed to open path in Finder")?;
-
-    Ok(())
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if url.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(url))
+    }
 }
 
-// ============================================================================
-// Git Reset
-// ============================================================================
-pub fn reset(repo_path: &Path, commit: &str, mode: &str) -> Result<()> {
-    let mode_flag = match mode.to_lowercase().as_str() {
-        "soft" => "--soft",
-        "mixed" => "--mixed",
-        "hard" => "--hard",
-        "keep" => "--keep",
-        _ => "--mixed", // default
-    };
-
+pub fn get_remotes(repo_path: &Path) -> Result<Vec<Remote>> {
     let output = Command::new("git")
+        .args(["remote", "-v"])
         .current_dir(repo_path)
-        .args(&["reset", mode_flag, commit])
         .output()
-        .context("Failed to execute git reset")?;
+        .context("Failed to execute git remote -v")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git reset failed: {}",
+            "Git remote failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    Ok(())
+    let mut remotes: Vec<Remote> = Vec::new();
+    // "<name>\t<url> (fetch)" and one "<name>\t<url> (push)" line per push URL
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((name, rest)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some((url, kind)) = rest.rsplit_once(' ') else {
+            continue;
+        };
+
+        let index = match remotes.iter().position(|remote| remote.name == name) {
+            Some(index) => index,
+            None => {
+                remotes.push(Remote {
+                    name: name.to_string(),
+                    fetch_url: String::new(),
+                    push_urls: Vec::new(),
+                });
+                remotes.len() - 1
+            }
+        };
+        match kind {
+            "(fetch)" => remotes[index].fetch_url = url.to_string(),
+            "(push)" => remotes[index].push_urls.push(url.to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(remotes)
 }
 
-// ============================================================================
-// Git Cherry-Pick
-// ============================================================================
-pub fn cherry_pick(repo_path: &Path, commit: &str) -> Result<String> {
-    let output = git_command()
-        .current_dir(repo_path)
-        .args(&["cherry-pick", commit])
-        .output()
-        .context("Failed to execute git cherry-pick")?;
+/// Adds a remote, optionally fetching it right away.
+pub fn add_remote(repo_path: &Path, name: &str, url: &str, fetch: bool) -> Result<()> {
+    let name = name.trim();
+    let url = url.trim();
+    if name.is_empty() || url.is_empty() {
+        anyhow::bail!("Remote name and URL are required");
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git cherry-pick failed: {}", stderr);
+    let mut args = vec!["remote", "add"];
+    if fetch {
+        args.push("-f");
     }
+    args.extend(["--", name, url]);
+    run_remote_command(repo_path, &args)
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+/// Renames a remote, along with its remote-tracking branches and upstream settings.
+pub fn rename_remote(repo_path: &Path, old_name: &str, new_name: &str) -> Result<()> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        anyhow::bail!("Remote name is required");
+    }
+    run_remote_command(repo_path, &["remote", "rename", "--", old_name, new_name])
 }
 
-// ============================================================================
-// Git Revert
-// ============================================================================
-pub fn revert(repo_path: &Path, commit: &str) -> Result<String> {
-    let output = git_command()
-        .current_dir(repo_path)
-        .args(&["revert", "--no-edit", commit])
-        .output()
-        .context("Failed to execute git revert")?;
+pub fn remove_remote(repo_path: &Path, name: &str) -> Result<()> {
+    run_remote_command(repo_path, &["remote", "remove", "--", name])
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git revert failed: {}", stderr);
+/// Changes the fetch URL, or the push URL when `push` is set (which then differs from fetch).
+pub fn set_remote_url(repo_path: &Path, name: &str, url: &str, push: bool) -> Result<()> {
+    let url = url.trim();
+    if url.is_empty() {
+        anyhow::bail!("Remote URL is required");
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    let mut args = vec!["remote", "set-url"];
+    if push {
+        args.push("--push");
+    }
+    args.extend(["--", name, url]);
+    run_remote_command(repo_path, &args)
 }
 
-// ============================================================================
-// Git Checkout Commit (detached HEAD)
-// ============================================================================
-pub fn checkout_commit(repo_path: &Path, commit: &str) -> Result<()> {
+fn run_remote_command(repo_path: &Path, args: &[&str]) -> Result<()> {
     let output = git_command()
+        .args(args)
         .current_dir(repo_path)
-        .args(&["checkout", commit])
         .output()
-        .context("Failed to execute git checkout")?;
+        .context("Failed to execute git remote")?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git checkout failed: {}", stderr);
+        anyhow::bail!(
+            "Git remote failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 
     Ok(())
 }
 
-// ============================================================================
-// Git Create Tag
-// ============================================================================
-pub fn create_tag(repo_path: &Path, name: &str, commit: &str, message: Option<&str>) -> Result<()> {
-    let output = if let Some(msg) = message {
-        // Annotated tag with message
-        Command::new("git")
-            .current_dir(repo_path)
-            .args(&["tag", "-a", name, commit, "-m", msg])
-            .output()
-            .context("Failed to execute git tag")?
-    } else {
-        // Lightweight tag
-        Command::new("git")
-            .current_dir(repo_path)
-            .args(&["tag", name, commit])
-            .output()
-            .context("Failed to execute git tag")?
-    };
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git tag failed: {}", stderr);
+/// Remote used when none is given: the branch's push or tracking remote, then
+/// `remote.pushDefault`, then "origin", then the only configured remote.
+fn default_remote(repo_path: &Path, branch: Option<&str>) -> Result<String> {
+    let mut keys = Vec::new();
+    if let Some(branch) = branch {
+        keys.push(format!("branch.{}.pushRemote", branch));
+        keys.push(format!("branch.{}.remote", branch));
     }
+    keys.push("remote.pushDefault".to_string());
 
-    Ok(())
-}
-
-// ============================================================================
-// Git Diff for Compare with Local
-// ============================================================================
-pub fn get_commit_diff(repo_path: &Path, commit: &str) -> Result<String> {
-    let output = Command::new("git")
-        .current_dir(repo_path)
-        .args(&["diff", commit, "HEAD"])
-        .output()
-        .context("Failed to execute git diff")?;
+    for key in keys {
+        // "." means the branch tracks another local branch
+        if let Some(remote) = config_value(repo_path, &key).filter(|r| r != ".") {
+            return Ok(remote);
+        }
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git diff failed: {}", stderr);
+    let remotes = get_remotes(repo_path)?;
+    if remotes.iter().any(|remote| remote.name == "origin") {
+        return Ok("origin".to_string());
     }
+    match remotes.as_slice() {
+        [only] => Ok(only.name.clone()),
+        [] => anyhow::bail!("No remote is configured for this repository"),
+        _ => anyhow::bail!("Several remotes are configured; choose which one to use"),
+    }
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+/// The remote and branch name the local `branch` tracks, e.g. ("upstream", "main").
+fn branch_upstream(repo_path: &Path, branch: &str) -> Option<(String, String)> {
+    let remote = config_value(repo_path, &format!("branch.{}.remote", branch))?;
+    let merge = config_value(repo_path, &format!("branch.{}.merge", branch))?;
+    let merge = merge
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&merge)
+        .to_string();
+    Some((remote, merge))
 }
 
-pub fn is_git_repo(repo_path: &Path) -> bool {
-    repo_path.join(".git").exists()
+/// Splits "upstream/feature/x" into ("upstream", "feature/x") using the configured remotes,
+/// so remote names containing slashes are handled too.
+fn split_remote_ref(repo_path: &Path, remote_ref: &str) -> Option<(String, String)> {
+    let remotes = get_remotes(repo_path).ok()?;
+    remotes
+        .iter()
+        .filter_map(|remote| {
+            let branch = remote_ref.strip_prefix(&format!("{}/", remote.name))?;
+            (!branch.is_empty()).then(|| (remote.name.clone(), branch.to_string()))
+        })
+        .max_by_key(|(remote, _)| remote.len())
 }
 
-fn repo_has_commits(repo_path: &Path) -> Result<bool> {
+fn config_value(repo_path: &Path, key: &str) -> Option<String> {
     let output = Command::new("git")
-        .args(&["rev-parse", "--verify", "HEAD"])
+        .args(["config", "--get", key])
         .current_dir(repo_path)
         .output()
-        .context("Failed to check repository commits")?;
-
-    Ok(output.status.success())
-}
-
-pub fn init_repository(options: &InitRepoOptions) -> Result<InitRepoResult> {
-    let repo_path = PathBuf::from(&options.path);
-    if !repo_path.exists() {
-        anyhow::bail!("Repository path does not exist");
-    }
-    if !repo_path.is_dir() {
-        anyhow::bail!("Repository path is not a directory");
-    }
-    if is_git_repo(&repo_path) {
-        anyhow::bail!("Repository already initialized");
-    }
-
-    let repo_name = options.name.trim();
-    if repo_name.is_empty() {
-        anyhow::bail!("Repository name is required");
-    }
+        .ok()?;
 
-    let branch = options.default_branch.trim();
-    if branch.is_empty() {
-        anyhow::bail!("Default branch is required");
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !value.is_empty() {
+        Some(value)
+    } else {
+        None
     }
+}
 
-    let init_output = Command::new("git")
-        .args(&["init", "--initial-branch", branch])
-        .current_dir(&repo_path)
+pub fn get_commit_shortstat(repo_path: &PathBuf, hash: &str) -> Result<CommitShortStat> {
+    let hash = hash.trim();
+    let output = Command::new("git")
+        .args(&["show", "--shortstat", "--format=", "--no-color", hash])
+        .current_dir(repo_path)
         .output()
-        .context("Failed to execute git init")?;
+        .context("Failed to execute git show --shortstat")?;
 
-    if !init_output.status.success() {
+    if !output.status.success() {
         anyhow::bail!(
-            "Git init failed: {}",
-            String::from_utf8_lossy(&init_output.stderr)
+            "Git show failed: {}",
+            String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    let mut created_files = Vec::new();
-    let mut skipped_files = Vec::new();
-
-    if options.add_readme {
-        let readme_path = repo_path.join("README.md");
-        if readme_path.exists() {
-            skipped_files.push("README.md".to_string());
-        } else {
-            let mut content = format!("# {}", repo_name);
-            if let Some(description) = options
-                .description
-                .as_ref()
-                .map(|value| value.trim())
-                .filter(|value| !value.is_empty())
-            {
-                content.push_str("\n\n");
-                content.push_str(description);
-            }
-            content.push('\n');
-            std::fs::write(&readme_path, content).with_context(|| "Failed to write README.md")?;
-            created_files.push("README.md".to_string());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut shortstat = CommitShortStat {
+        files_changed: None,
+        insertions: None,
+        deletions: None,
+    };
+
+    for line in stdout.lines() {
+        let trimmed = line.trim();
+        if !(trimmed.contains("file changed") || trimmed.contains("files changed")) {
+            continue;
         }
-    }
 
-    if let Some(template) = options.gitignore_template.as_ref() {
-        if let Some(content) = gitignore_template(template) {
-            let gitignore_path = repo_path.join(".gitignore");
-            if gitignore_path.exists() {
-                skipped_files.push(".gitignore".to_string());
-            } else {
-                std::fs::write(&gitignore_path, content)
-                    .with_context(|| "Failed to write .gitignore")?;
-                created_files.push(".gitignore".to_string());
+        for part in trimmed.split(',') {
+            let part = part.trim();
+            let Some(first_token) = part.split_whitespace().next() else {
+                continue;
+            };
+
+            let Ok(value) = first_token.parse::<u32>() else {
+                continue;
+            };
+
+            if part.contains("file changed") || part.contains("files changed") {
+                shortstat.files_changed = Some(value);
+            } else if part.contains("insertion") {
+                shortstat.insertions = Some(value);
+            } else if part.contains("deletion") {
+                shortstat.deletions = Some(value);
             }
         }
+
+        break;
     }
 
-    if let Some(license_id) = options.license.as_ref() {
-        if let Some(content) = license_text(license_id) {
-            let license_path = repo_path.join("LICENSE");
-            if license_path.exists() {
-                skipped_files.push("LICENSE".to_string());
-            } else {
-                std::fs::write(&license_path, content)
-                    .with_context(|| "Failed to write LICENSE")?;
-                created_files.push("LICENSE".to_string());
-            }
-        }
+    Ok(shortstat)
+}
+
+/// Cria branch local a partir de remota com tracking e faz checkout
+/// remote_ref: "origin/feature/x" → cria "feature/x" com upstream
+pub fn checkout_remote_branch(repo_path: &PathBuf, remote_ref: &str) -> Result<()> {
+    // Deriva nome local: "origin/feature/x" → "feature/x"
+    let local_name = match split_remote_ref(repo_path, remote_ref) {
+        Some((_, branch)) => branch,
+        None => match remote_ref.find('/') {
+            Some(pos) => remote_ref[pos + 1..].to_string(),
+            None => remote_ref.to_string(),
+        },
+    };
+
+    // Cria branch local com tracking e faz checkout
+    // Nota: UI garante que branch local não existe (filtro no dropdown)
+    let output = git_command()
+        .args(["checkout", "-b", &local_name, "--track", remote_ref])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to create and checkout branch")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git checkout -b failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 
-    if options.initial_commit {
-        let add_output = Command::new("git")
-            .args(&["add", "-A"])
-            .current_dir(&repo_path)
+    Ok(())
+}
+
+pub fn create_branch(
+    repo_path: &Path,
+    name: &str,
+    from: Option<&str>,
+    push_to_remote: bool,
+    remote: Option<&str>,
+    checkout: bool,
+) -> Result<()> {
+    if checkout {
+        // Original behavior: create and checkout
+        let mut cmd = Command::new("git");
+        cmd.current_dir(repo_path)
+            .arg("checkout")
+            .arg("-b")
+            .arg(name);
+        if let Some(base) = from {
+            cmd.arg(base);
+        }
+
+        let output = cmd
             .output()
-            .context("Failed to stage initial files")?;
+            .context("Failed to execute git checkout -b for new branch")?;
 
-        if !add_output.status.success() {
+        if !output.status.success() {
             anyhow::bail!(
-                "Git add failed: {}",
-                String::from_utf8_lossy(&add_output.stderr)
+                "Git checkout -b failed: {}",
+                String::from_utf8_lossy(&output.stderr)
             );
         }
+    } else {
+        // New behavior: create branch without switching
+        let mut cmd = Command::new("git");
+        cmd.current_dir(repo_path).arg("branch").arg(name);
+        if let Some(base) = from {
+            cmd.arg(base);
+        }
 
-        let message = options
-            .commit_message
-            .as_deref()
-            .map(|value| value.trim())
-            .filter(|value| !value.is_empty())
-            .unwrap_or("chore: init repo");
+        let output = cmd
+            .output()
+            .context("Failed to execute git branch for new branch")?;
 
-        let commit_output = Command::new("git")
-            .args(&["commit", "--allow-empty", "-m", message])
-            .current_dir(&repo_path)
+        if !output.status.success() {
+            anyhow::bail!(
+                "Git branch failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    if push_to_remote {
+        let remote = match remote {
+            Some(remote) => remote.to_string(),
+            None => default_remote(repo_path, Some(name))?,
+        };
+        let push_output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["push", "-u", &remote, name])
             .output()
-            .context("Failed to commit initial files")?;
+            .context("Failed to push new branch")?;
 
-        if !commit_output.status.success() {
+        if !push_output.status.success() {
             anyhow::bail!(
-                "Git commit failed: {}",
-                String::from_utf8_lossy(&commit_output.stderr)
+                "Git push failed: {}",
+                String::from_utf8_lossy(&push_output.stderr)
             );
         }
     }
 
-    Ok(InitRepoResult {
-        created_files,
-        skipped_files,
-    })
+    Ok(())
 }
 
-pub fn publish_github_repo(options: &PublishRepoOptions) -> Result<PublishRepoResult> {
-    let repo_path = PathBuf::from(&options.path);
-    if !repo_path.exists() {
-        anyhow::bail!("Repository path does not exist");
-    }
-    if !repo_path.is_dir() {
-        anyhow::bail!("Repository path is not a directory");
-    }
-    if !is_git_repo(&repo_path) {
-        anyhow::bail!("Repository is not initialized");
-    }
+/// Deletes a local or remote branch. Branches matching `protected_branches` are refused.
+pub fn delete_branch(
+    repo_path: &Path,
+    name: &str,
+    force: bool,
+    is_remote: bool,
+    protected_branches: &[String],
+) -> Result<()> {
+    let name = name.trim();
 
-    let repo_name = options.name.trim();
-    if repo_name.is_empty() {
-        anyhow::bail!("Repository name is required");
+    if is_remote {
+        let (remote, branch_name) = match split_remote_ref(repo_path, name) {
+            Some(parts) => parts,
+            None => (default_remote(repo_path, None)?, name.to_string()),
+        };
+        if is_protected_branch(&branch_name, protected_branches) {
+            anyhow::bail!(
+                "'{}' is a protected branch; deleting it is not allowed",
+                branch_name
+            );
+        }
+
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["push", &remote, "--delete", &branch_name])
+            .output()
+            .context("Failed to delete remote branch")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to delete remote branch: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        return Ok(());
     }
 
-    if get_remote_origin_url(&repo_path)?.is_some() {
-        anyhow::bail!("Repository already has a remote origin");
+    if is_protected_branch(name, protected_branches) {
+        anyhow::bail!(
+            "'{}' is a protected branch; deleting it is not allowed",
+            name
+        );
     }
 
-    if !repo_has_commits(&repo_path)? {
-        anyhow::bail!("Repository has no commits. Create one before publishing.");
+    let flag = if force { "-D" } else { "-d" };
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["branch", flag, name])
+        .output()
+        .context("Failed to delete local branch")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to delete local branch: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 
-    let visibility_flag = match options.visibility.trim().to_lowercase().as_str() {
-        "private" => "--private",
-        _ => "--public",
+    Ok(())
+}
+
+/// Local branches whose upstream is gone or that are fully merged into `base` (the
+/// default branch when `None`). A merged branch still pointing at the base tip has no work
+/// of its own yet, so it isn't listed. The current branch, the base, branches checked out
+/// in another worktree and protected branches are never listed.
+pub fn get_stale_branches(
+    repo_path: &Path,
+    base: Option<&str>,
+    protected_branches: &[String],
+) -> Result<Vec<StaleBranch>> {
+    let base = match base.map(str::trim).filter(|base| !base.is_empty()) {
+        Some(base) => Some(base.to_string()),
+        None => get_default_branch(repo_path),
+    };
+    let merged = match base.as_deref() {
+        Some(base) => merged_branches(repo_path, base)?,
+        None => HashSet::new(),
     };
+    let base_name = base
+        .as_deref()
+        .map(|base| base.strip_prefix("refs/heads/").unwrap_or(base));
+    let base_tip = base
+        .as_deref()
+        .and_then(|base| resolve_revision(repo_path, &format!("{base}^{{commit}}")));
+    let checked_out: HashSet<String> = get_worktrees(&repo_path.to_path_buf())?
+        .into_iter()
+        .map(|worktree| worktree.branch)
+        .collect();
 
-    let mut args = vec![
-        "repo",
-        "create",
-        repo_name,
-        visibility_flag,
-        "--source",
-        ".",
-        "--push",
-        "--confirm",
-    ];
+    Ok(get_branches(repo_path)?
+        .into_iter()
+        .filter(|branch| !branch.remote && !branch.current)
+        .filter(|branch| Some(branch.name.as_str()) != base_name)
+        .filter(|branch| !checked_out.contains(&branch.name))
+        .filter(|branch| !is_protected_branch(&branch.name, protected_branches))
+        .filter_map(|branch| {
+            let merged = merged.contains(&branch.name);
+            let at_base_tip =
+                branch.last_commit.as_ref().map(|commit| &commit.hash) == base_tip.as_ref();
+            (branch.upstream_gone || (merged && !at_base_tip)).then_some(StaleBranch {
+                name: branch.name,
+                upstream_gone: branch.upstream_gone,
+                merged,
+                last_commit: branch.last_commit,
+            })
+        })
+        .collect())
+}
 
-    if let Some(description) = options
-        .description
-        .as_ref()
-        .map(|value| value.trim())
-        .filter(|value| !value.is_empty())
-    {
-        args.push("--description");
-        args.push(description);
-    }
+/// Local branches reachable from `base`.
+fn merged_branches(repo_path: &Path, base: &str) -> Result<HashSet<String>> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            &format!("--merged={base}"),
+            "--format=%(refname:strip=2)",
+            "refs/heads",
+        ])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git for-each-ref")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git for-each-ref failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Deletes the selected stale branches (or only reports what would happen on a dry run).
+/// Names that are no longer stale are skipped, as are branches with commits outside
+/// `base` unless `force` is set.
+pub fn delete_stale_branches(
+    repo_path: &Path,
+    names: &[String],
+    base: Option<&str>,
+    force: bool,
+    dry_run: bool,
+    protected_branches: &[String],
+) -> Result<BranchCleanupResult> {
+    let stale = get_stale_branches(repo_path, base, protected_branches)?;
+    let mut result = BranchCleanupResult {
+        dry_run,
+        deleted: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    for name in names {
+        let Some(branch) = stale.iter().find(|branch| &branch.name == name) else {
+            result.skipped.push(SkippedBranch {
+                name: name.clone(),
+                reason: "Not a stale branch".to_string(),
+            });
+            continue;
+        };
+
+        if !branch.merged && !force {
+            result.skipped.push(SkippedBranch {
+                name: name.clone(),
+                reason: "Has commits that are not merged; delete with force to discard them"
+                    .to_string(),
+            });
+            continue;
+        }
+
+        if !dry_run {
+            // Merged branches were checked against the base ourselves, so `git branch -d`
+            // (which checks against HEAD or the upstream) must not get in the way
+            if let Err(err) = delete_branch(repo_path, name, true, false, protected_branches) {
+                result.skipped.push(SkippedBranch {
+                    name: name.clone(),
+                    reason: err.to_string(),
+                });
+                continue;
+            }
+        }
+        result.deleted.push(branch.clone());
+    }
+
+    Ok(result)
+}
+
+/// Renames a local branch. With `rename_remote`, a published branch is renamed on its
+/// remote too: the new name is pushed and tracked, then the old remote branch is deleted.
+pub fn rename_branch(
+    repo_path: &Path,
+    old_name: &str,
+    new_name: &str,
+    rename_remote: bool,
+    protected_branches: &[String],
+) -> Result<()> {
+    let old_name = old_name.trim();
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        anyhow::bail!("Branch name is required");
+    }
+
+    let upstream = if rename_remote {
+        branch_upstream(repo_path, old_name).filter(|(remote, _)| remote != ".")
+    } else {
+        None
+    };
+    if let Some((_, remote_branch)) = &upstream {
+        if is_protected_branch(remote_branch, protected_branches) {
+            anyhow::bail!(
+                "'{}' is a protected branch; renaming it on the remote is not allowed",
+                remote_branch
+            );
+        }
+    }
+
+    let output = Command::new("git")
+        .args(["branch", "-m", "--", old_name, new_name])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git branch -m")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git branch failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let Some((remote, old_remote_branch)) = upstream else {
+        return Ok(());
+    };
+
+    let output = git_command()
+        .args([
+            "push",
+            "--set-upstream",
+            &remote,
+            &format!("refs/heads/{new_name}:refs/heads/{new_name}"),
+        ])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git push")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Branch renamed locally, but pushing '{}' to {} failed: {}",
+            new_name,
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let output = git_command()
+        .args(["push", &remote, "--delete", &old_remote_branch])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git push")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Branch renamed and pushed, but deleting '{}' on {} failed: {}",
+            old_remote_branch,
+            remote,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Sets or changes the branch's upstream, e.g. "origin/main".
+pub fn set_branch_upstream(repo_path: &Path, branch: &str, upstream: &str) -> Result<()> {
+    let upstream = upstream.trim();
+    if upstream.is_empty() {
+        anyhow::bail!("Upstream branch is required");
+    }
+    run_branch_command(
+        repo_path,
+        &[&format!("--set-upstream-to={upstream}"), "--", branch],
+    )
+}
+
+pub fn unset_branch_upstream(repo_path: &Path, branch: &str) -> Result<()> {
+    run_branch_command(repo_path, &["--unset-upstream", "--", branch])
+}
+
+fn run_branch_command(repo_path: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("branch")
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git branch")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git branch failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Discard with Undo
+// ============================================================================
+
+const DISCARD_SNAPSHOT_DIR: &str = "everydaygit-discard";
+const MAX_DISCARD_SNAPSHOTS: usize = 50;
+/// Largest clean that includes ignored files (dependencies, build output) we snapshot.
+const MAX_IGNORED_CLEAN_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Discards unstaged changes to tracked files, restoring them from the index.
+/// The current content is snapshotted first (see `undo_discard`).
+pub fn discard_files(repo_path: &Path, paths: &[String]) -> Result<DiscardSnapshot> {
+    if paths.is_empty() {
+        anyhow::bail!("No files to discard");
+    }
+    for path in paths {
+        if !is_tracked(repo_path, path)? {
+            anyhow::bail!("{path} is untracked; use clean to remove untracked files");
+        }
+    }
+
+    // Directories and globs are expanded, so exactly the files that get snapshotted are restored
+    let files = changed_tracked_files(repo_path, paths)?;
+    if files.is_empty() {
+        anyhow::bail!("No changes to discard");
+    }
+
+    let snapshot = create_discard_snapshot(repo_path, DiscardKind::Files, &files)?;
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["--literal-pathspecs", "restore", "--worktree", "--"])
+        .args(&files)
+        .output()
+        .context("Failed to execute git restore")?;
+
+    if !output.status.success() {
+        let _ = delete_discard_snapshot(repo_path, &snapshot.id);
+        anyhow::bail!(
+            "Git restore failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(snapshot)
+}
+
+/// Tracked files under `paths` that are modified or deleted in the working tree.
+/// Submodules are left out: restoring them would not discard their changes anyway.
+fn changed_tracked_files(repo_path: &Path, paths: &[String]) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["ls-files", "--modified", "--deleted", "-z", "--"])
+        .args(paths)
+        .output()
+        .context("Failed to list modified files")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git ls-files failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let mut seen = HashSet::new();
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty() && seen.insert(path.to_string()))
+        .filter(|path| {
+            !repo_path
+                .join(path)
+                .symlink_metadata()
+                .is_ok_and(|metadata| metadata.is_dir())
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+/// Removes untracked files (all of them when `paths` is empty, otherwise those under `paths`).
+/// Ignored files are only included with `include_ignored`. Files are snapshotted first.
+pub fn clean_untracked(
+    repo_path: &Path,
+    paths: &[String],
+    include_ignored: bool,
+) -> Result<DiscardSnapshot> {
+    let mut args = vec!["ls-files", "--others", "-z"];
+    if !include_ignored {
+        args.push("--exclude-standard");
+    }
+    args.push("--");
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&args)
+        .args(paths)
+        .output()
+        .context("Failed to list untracked files")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git ls-files failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Nested repositories are listed as "dir/"; leave them alone
+    let files: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty() && !path.ends_with('/'))
+        .map(str::to_string)
+        .collect();
+
+    if files.is_empty() {
+        anyhow::bail!("No untracked files to clean");
+    }
+
+    if include_ignored {
+        let size: u64 = files
+            .iter()
+            .filter_map(|file| repo_path.join(file).symlink_metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        if size > MAX_IGNORED_CLEAN_BYTES {
+            anyhow::bail!(
+                "The files to clean take {} MB, more than the {} MB that can be kept for undo. Choose fewer paths or leave ignored files out.",
+                size.div_ceil(1024 * 1024),
+                MAX_IGNORED_CLEAN_BYTES / (1024 * 1024)
+            );
+        }
+    }
+
+    let snapshot = create_discard_snapshot(repo_path, DiscardKind::Untracked, &files)?;
+
+    let mut failed = Vec::new();
+    for file in &files {
+        let full_path = repo_path.join(file);
+        match std::fs::remove_file(&full_path) {
+            Ok(()) => remove_empty_parents(repo_path, &full_path),
+            Err(err) => failed.push(format!("{file}: {err}")),
+        }
+    }
+
+    // All or nothing: put back what was already removed
+    if !failed.is_empty() {
+        let mut message = format!("Failed to clean untracked files:\n{}", failed.join("\n"));
+        if let Err(err) = undo_discard(repo_path, &snapshot.id) {
+            message.push_str(&format!(
+                "\nPutting back the removed files also failed: {err:#}\nThey are kept in snapshot {}.",
+                snapshot.id
+            ));
+        }
+        anyhow::bail!(message);
+    }
+
+    Ok(snapshot)
+}
+
+/// Snapshots, newest first.
+pub fn get_discard_snapshots(repo_path: &Path) -> Result<Vec<DiscardSnapshot>> {
+    let root = git_dir_path(repo_path, DISCARD_SNAPSHOT_DIR)?;
+    let Ok(entries) = std::fs::read_dir(&root) else {
+        return Ok(Vec::new());
+    };
+
+    let mut snapshots: Vec<DiscardSnapshot> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("manifest.json")).ok())
+        .filter_map(|manifest| serde_json::from_str(&manifest).ok())
+        .collect();
+    snapshots.sort_by(|a, b| b.id.cmp(&a.id));
+
+    Ok(snapshots)
+}
+
+/// Puts the snapshotted content back into the working tree and removes the snapshot.
+/// Files are overwritten with their content from the moment of the discard.
+pub fn undo_discard(repo_path: &Path, snapshot_id: &str) -> Result<()> {
+    let dir = discard_snapshot_dir(repo_path, snapshot_id)?;
+    let manifest = std::fs::read_to_string(dir.join("manifest.json"))
+        .with_context(|| format!("Snapshot {snapshot_id} not found"))?;
+    let snapshot: DiscardSnapshot =
+        serde_json::from_str(&manifest).context("Failed to parse snapshot manifest")?;
+
+    for file in &snapshot.files {
+        let target = repo_path.join(&file.path);
+
+        if !file.existed {
+            if target.symlink_metadata().is_ok() {
+                std::fs::remove_file(&target)
+                    .with_context(|| format!("Failed to remove {}", file.path))?;
+            }
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory for {}", file.path))?;
+        }
+        if target.symlink_metadata().is_ok() {
+            std::fs::remove_file(&target)
+                .with_context(|| format!("Failed to replace {}", file.path))?;
+        }
+
+        match &file.symlink_target {
+            Some(link) => restore_symlink(link, &target)
+                .with_context(|| format!("Failed to restore link {}", file.path))?,
+            None => {
+                std::fs::copy(dir.join("files").join(&file.path), &target)
+                    .with_context(|| format!("Failed to restore {}", file.path))?;
+                set_executable(&target, file.executable)?;
+            }
+        }
+    }
+
+    delete_discard_snapshot(repo_path, snapshot_id)
+}
+
+pub fn delete_discard_snapshot(repo_path: &Path, snapshot_id: &str) -> Result<()> {
+    let dir = discard_snapshot_dir(repo_path, snapshot_id)?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .with_context(|| format!("Failed to delete snapshot {snapshot_id}"))?;
+    }
+    Ok(())
+}
+
+fn discard_snapshot_dir(repo_path: &Path, snapshot_id: &str) -> Result<PathBuf> {
+    if snapshot_id.is_empty()
+        || !snapshot_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        anyhow::bail!("Invalid snapshot id: {snapshot_id}");
+    }
+    Ok(git_dir_path(repo_path, DISCARD_SNAPSHOT_DIR)?.join(snapshot_id))
+}
+
+fn create_discard_snapshot(
+    repo_path: &Path,
+    kind: DiscardKind,
+    paths: &[String],
+) -> Result<DiscardSnapshot> {
+    let now = Utc::now();
+    let base_id = now.format("%Y%m%d%H%M%S%3f").to_string();
+    let mut id = base_id.clone();
+    let mut dir = discard_snapshot_dir(repo_path, &id)?;
+    let mut attempt = 1;
+    while dir.exists() {
+        id = format!("{base_id}-{attempt}");
+        dir = discard_snapshot_dir(repo_path, &id)?;
+        attempt += 1;
+    }
+
+    let files_dir = dir.join("files");
+    std::fs::create_dir_all(&files_dir).context("Failed to create snapshot directory")?;
+
+    // A half-written snapshot would show up as one that can't be undone
+    let snapshot = match write_discard_snapshot(repo_path, &dir, id, now, kind, paths) {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(err);
+        }
+    };
+
+    // Keep the most recent snapshots only
+    let snapshots = get_discard_snapshots(repo_path)?;
+    for old in snapshots.iter().skip(MAX_DISCARD_SNAPSHOTS) {
+        let _ = delete_discard_snapshot(repo_path, &old.id);
+    }
+
+    Ok(snapshot)
+}
+
+/// Copies `paths` into the snapshot directory `dir` and writes its manifest.
+fn write_discard_snapshot(
+    repo_path: &Path,
+    dir: &Path,
+    id: String,
+    now: DateTime<Utc>,
+    kind: DiscardKind,
+    paths: &[String],
+) -> Result<DiscardSnapshot> {
+    let mut files = Vec::new();
+    for path in paths {
+        let source = repo_path.join(path);
+        let Ok(metadata) = source.symlink_metadata() else {
+            files.push(SnapshotFile {
+                path: path.clone(),
+                existed: false,
+                executable: false,
+                symlink_target: None,
+            });
+            continue;
+        };
+
+        if metadata.file_type().is_symlink() {
+            let link = std::fs::read_link(&source)
+                .with_context(|| format!("Failed to read link {path}"))?;
+            files.push(SnapshotFile {
+                path: path.clone(),
+                existed: true,
+                executable: false,
+                symlink_target: Some(link.to_string_lossy().to_string()),
+            });
+            continue;
+        }
+        if !metadata.is_file() {
+            // Anything else can't be put back, so it must not be discarded either
+            anyhow::bail!("{path} is not a file and can't be snapshotted");
+        }
+
+        let target = dir.join("files").join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create snapshot directory")?;
+        }
+        std::fs::copy(&source, &target).with_context(|| format!("Failed to snapshot {path}"))?;
+        files.push(SnapshotFile {
+            path: path.clone(),
+            existed: true,
+            executable: is_executable(&metadata),
+            symlink_target: None,
+        });
+    }
+
+    let snapshot = DiscardSnapshot {
+        id,
+        created_at: now.to_rfc3339(),
+        kind,
+        files,
+    };
+    let manifest =
+        serde_json::to_string_pretty(&snapshot).context("Failed to serialize snapshot")?;
+    std::fs::write(dir.join("manifest.json"), manifest).context("Failed to write snapshot")?;
+
+    Ok(snapshot)
+}
+
+/// Removes directories left empty by a clean, stopping at the repository root.
+fn remove_empty_parents(repo_path: &Path, file_path: &Path) {
+    let mut current = file_path.parent();
+    while let Some(dir) = current {
+        if dir == repo_path || !dir.starts_with(repo_path) || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    permissions.set_mode(if executable {
+        mode | 0o111
+    } else {
+        mode & !0o111
+    });
+    std::fs::set_permissions(path, permissions).context("Failed to restore file mode")
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn restore_symlink(link: &str, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(link, target)
+}
+
+#[cfg(not(unix))]
+fn restore_symlink(link: &str, target: &Path) -> std::io::Result<()> {
+    // Without symlink support git checks links out as plain files holding the target
+    std::fs::write(target, link)
+}
+
+// ============================================================================
+// Git Worktrees
+// ============================================================================
+
+/// List all worktrees for the repository
+pub fn get_worktrees(repo_path: &PathBuf) -> Result<Vec<Worktree>> {
+    let output = Command::new("git")
+        .args(&["worktree", "list", "--porcelain"])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git worktree list")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git worktree list failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut worktrees = Vec::new();
+    let mut current_path: Option<String> = None;
+    let mut current_branch: Option<String> = None;
+    let mut is_first = true;
+
+    for line in stdout.lines() {
+        if line.starts_with("worktree ") {
+            // Save previous worktree if exists
+            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
+                worktrees.push(Worktree {
+                    path,
+                    branch,
+                    is_main: is_first,
+                });
+                is_first = false;
+            }
+            current_path = Some(line.trim_start_matches("worktree ").to_string());
+            current_branch = None;
+        } else if line.starts_with("branch ") {
+            // Format: "branch refs/heads/branch-name"
+            let branch_ref = line.trim_start_matches("branch ");
+            let branch_name = branch_ref
+                .strip_prefix("refs/heads/")
+                .unwrap_or(branch_ref)
+                .to_string();
+            current_branch = Some(branch_name);
+        } else if line.starts_with("HEAD ") {
+            // Detached HEAD - use abbreviated hash as branch name
+            if current_branch.is_none() {
+                let hash = line.trim_start_matches("HEAD ");
+                current_branch = Some(format!("(detached {})", &hash[..7.min(hash.len())]));
+            }
+        } else if line.trim().is_empty() {
+            // End of a worktree entry
+            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
+                worktrees.push(Worktree {
+                    path,
+                    branch,
+                    is_main: is_first,
+                });
+                is_first = false;
+            }
+        }
+    }
+
+    // Handle last worktree if no trailing newline
+    if let (Some(path), Some(branch)) = (current_path, current_branch) {
+        worktrees.push(Worktree {
+            path,
+            branch,
+            is_main: is_first,
+        });
+    }
+
+    Ok(worktrees)
+}
+
+/// Remove a worktree
+pub fn remove_worktree(repo_path: &PathBuf, worktree_path: &str, force: bool) -> Result<()> {
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(worktree_path);
+
+    let output = Command::new("git")
+        .args(&args)
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git worktree remove")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git worktree remove failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Open a path in Finder (macOS)
+pub fn open_path_in_finder(path: &str) -> Result<()> {
+    Command::new("open")
+        .arg(path)
+        .output()
+        .context("Failed to open path in Finder")?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Git Reset
+// ============================================================================
+pub fn reset(repo_path: &Path, commit: &str, mode: &str) -> Result<()> {
+    let mode_flag = match mode.to_lowercase().as_str() {
+        "soft" => "--soft",
+        "mixed" => "--mixed",
+        "hard" => "--hard",
+        "keep" => "--keep",
+        _ => "--mixed", // default
+    };
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["reset", mode_flag, commit])
+        .output()
+        .context("Failed to execute git reset")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git reset failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Git Cherry-Pick
+// ============================================================================
+pub fn cherry_pick(repo_path: &Path, commit: &str) -> Result<String> {
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(&["cherry-pick", commit])
+        .output()
+        .context("Failed to execute git cherry-pick")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git cherry-pick failed: {}", stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ============================================================================
+// Git Revert
+// ============================================================================
+pub fn revert(repo_path: &Path, commit: &str) -> Result<String> {
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(&["revert", "--no-edit", commit])
+        .output()
+        .context("Failed to execute git revert")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git revert failed: {}", stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ============================================================================
+// Git Checkout Commit (detached HEAD)
+// ============================================================================
+pub fn checkout_commit(repo_path: &Path, commit: &str) -> Result<()> {
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(&["checkout", commit])
+        .output()
+        .context("Failed to execute git checkout")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git checkout failed: {}", stderr);
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Files at a Revision
+// ============================================================================
+
+/// Reads a file as it was at `revision` (commit, branch, tag...) without touching the working tree.
+pub fn get_file_at_revision(
+    repo_path: &Path,
+    file_path: &str,
+    revision: &str,
+) -> Result<FileAtRevision> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args([
+            "ls-tree",
+            "-l",
+            "-z",
+            "--full-tree",
+            revision,
+            "--",
+            file_path,
+        ])
+        .output()
+        .context("Failed to execute git ls-tree")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git ls-tree failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let mut file = FileAtRevision {
+        path: file_path.to_string(),
+        revision: revision.to_string(),
+        exists: false,
+        blob: None,
+        mode: None,
+        size: None,
+        is_binary: false,
+        content: None,
+    };
+
+    // Format: "<mode> <type> <object> <size>\t<path>\0"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some((info, _)) = stdout
+        .split('\0')
+        .filter_map(|entry| entry.split_once('\t'))
+        .find(|(_, path)| *path == file_path)
+    else {
+        return Ok(file);
+    };
+
+    let fields: Vec<&str> = info.split_whitespace().collect();
+    let [mode, kind, object, size] = fields[..] else {
+        anyhow::bail!("Unexpected ls-tree output: {info}");
+    };
+    if kind != "blob" {
+        anyhow::bail!("{file_path} is a {kind} at {revision}, not a file");
+    }
+
+    file.exists = true;
+    file.blob = Some(object.to_string());
+    file.mode = Some(mode.to_string());
+    file.size = size.parse().ok();
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["cat-file", "blob", object])
+        .output()
+        .context("Failed to execute git cat-file")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git cat-file failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Same heuristic as git: a NUL byte in the first 8000 bytes means binary
+    if output.stdout.iter().take(8000).any(|byte| *byte == 0) {
+        file.is_binary = true;
+        return Ok(file);
+    }
+    match String::from_utf8(output.stdout) {
+        Ok(content) => file.content = Some(content),
+        Err(_) => file.is_binary = true,
+    }
+
+    Ok(file)
+}
+
+/// Restores paths from `source` into the working tree and/or the index. HEAD does not move.
+pub fn restore_paths(
+    repo_path: &Path,
+    source: &str,
+    paths: &[String],
+    worktree: bool,
+    staged: bool,
+) -> Result<()> {
+    if paths.is_empty() {
+        anyhow::bail!("No paths to restore");
+    }
+    if !worktree && !staged {
+        anyhow::bail!("Choose the working tree, the index, or both to restore into");
+    }
+
+    let mut args = vec!["restore".to_string(), format!("--source={source}")];
+    if worktree {
+        args.push("--worktree".to_string());
+    }
+    if staged {
+        args.push("--staged".to_string());
+    }
+    args.push("--".to_string());
+    args.extend(paths.iter().cloned());
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute git restore")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git restore failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Tags
+// ============================================================================
+
+/// Format parsed by `parse_tag_record`; the message body goes last so it may contain anything.
+/// `%(*objectname)` is only set for annotated tags (the commit behind the tag object).
+const TAG_FORMAT: &str = "--format=%(refname:strip=2)%1f%(objecttype)%1f%(objectname)%1f\
+%(*objectname)%1f%(taggername)%1f%(taggeremail:trim)%1f%(creatordate:iso)%1f\
+%(contents:subject)%1f%(contents:body)%1e";
+
+/// Lists tags, newest semantic version first; tags that aren't versions come last, by name.
+pub fn get_tags(repo_path: &Path) -> Result<Vec<Tag>> {
+    let mut tags = list_tags(repo_path, &["refs/tags"])?;
+    tags.sort_by_cached_key(|tag| tag_order_key(&tag.name));
+    Ok(tags)
+}
+
+fn tag_order_key(name: &str) -> (std::cmp::Reverse<Option<TagVersion>>, String) {
+    (std::cmp::Reverse(parse_tag_version(name)), name.to_string())
+}
+
+/// Shows a tag together with the commit it points at.
+pub fn show_tag(repo_path: &Path, name: &str) -> Result<TagDetails> {
+    let tag = list_tags(repo_path, &[&format!("refs/tags/{}", name)])?
+        .into_iter()
+        .find(|tag| tag.name == name)
+        .ok_or_else(|| anyhow!("Tag '{}' not found", name))?;
+
+    let output = Command::new("git")
+        .args([
+            "log",
+            "-1",
+            "--decorate=full",
+            COMMIT_LOG_FORMAT,
+            tag.target.as_str(),
+            "--",
+        ])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git log failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let commit = parse_commit_log(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Tag '{}' does not point at a commit", name))?;
+
+    Ok(TagDetails { tag, commit })
+}
+
+fn list_tags(repo_path: &Path, patterns: &[&str]) -> Result<Vec<Tag>> {
+    let output = Command::new("git")
+        .args(["for-each-ref", TAG_FORMAT])
+        .args(patterns)
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git for-each-ref")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git for-each-ref failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\u{001e}')
+        .filter_map(parse_tag_record)
+        .collect())
+}
+
+fn parse_tag_record(record: &str) -> Option<Tag> {
+    // for-each-ref ends every record with a newline, which lands before the next one
+    let record = record.trim_start_matches('\n');
+    if record.trim().is_empty() {
+        return None;
+    }
+
+    let parts: Vec<&str> = record.splitn(9, '\u{001f}').collect();
+    if parts.len() < 9 {
+        return None;
+    }
+
+    let annotated = parts[1] == "tag";
+    let optional = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    Some(Tag {
+        name: parts[0].to_string(),
+        target: if annotated && !parts[3].is_empty() {
+            parts[3].to_string()
+        } else {
+            parts[2].to_string()
+        },
+        annotated,
+        tagger: optional(parts[4]),
+        tagger_email: optional(parts[5]),
+        date: parts[6].trim().to_string(),
+        // Subject and body leave out the signature of signed tags
+        message: if annotated {
+            optional(&format!("{}\n\n{}", parts[7].trim(), parts[8].trim()))
+        } else {
+            None
+        },
+    })
+}
+
+/// Semantic version precedence: release numbers (`1.10` after `1.9`), then final releases
+/// after pre-releases (`1.0.0-rc.1` before `1.0.0`), then pre-release identifiers.
+type TagVersion = (Vec<u64>, bool, Vec<VersionIdentifier>);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum VersionIdentifier {
+    // Numeric identifiers have lower precedence than alphanumeric ones
+    Numeric(u64),
+    Text(String),
+}
+
+fn parse_tag_version(name: &str) -> Option<TagVersion> {
+    let version = name
+        .rsplit('/')
+        .next()
+        .unwrap_or(name)
+        .trim_start_matches(['v', 'V']);
+    // Build metadata doesn't take part in precedence
+    let version = version.split('+').next().unwrap_or(version);
+    let (release, pre_release) = match version.split_once('-') {
+        Some((release, pre_release)) => (release, Some(pre_release)),
+        None => (version, None),
+    };
+
+    let numbers = release
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let identifiers = pre_release
+        .map(|pre_release| {
+            pre_release
+                .split('.')
+                .map(|part| match part.parse::<u64>() {
+                    Ok(number) => VersionIdentifier::Numeric(number),
+                    Err(_) => VersionIdentifier::Text(part.to_string()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some((numbers, pre_release.is_none(), identifiers))
+}
+
+/// Deletes a tag locally and, when `remote` is given, on that remote too.
+pub fn delete_tag(repo_path: &Path, name: &str, remote: Option<&str>) -> Result<()> {
+    let exists_locally = resolve_revision(repo_path, &format!("refs/tags/{}", name)).is_some();
+    if !exists_locally && remote.is_none() {
+        anyhow::bail!("Tag '{}' not found", name);
+    }
+
+    if let Some(remote) = remote {
+        let output = git_command()
+            .args(["push", remote, "--delete", &format!("refs/tags/{}", name)])
+            .current_dir(repo_path)
+            .output()
+            .context("Failed to execute git push")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Git push failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    if exists_locally {
+        let output = Command::new("git")
+            .args(["tag", "-d", name])
+            .current_dir(repo_path)
+            .output()
+            .context("Failed to execute git tag")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Git tag failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Pushes one tag, or every local tag when `name` is None.
+pub fn push_tags(repo_path: &Path, remote: Option<&str>, name: Option<&str>) -> Result<String> {
+    let options = PushOptions {
+        remote: remote.map(str::to_string),
+        tag: name.map(str::to_string),
+        ..Default::default()
+    };
+    if options.tag.is_some() {
+        return push(repo_path, &options, &[]);
+    }
+
+    let remote = match remote {
+        Some(remote) => remote.to_string(),
+        None => default_remote(repo_path, current_branch_name(repo_path).as_deref())?,
+    };
+    let mut command = git_command();
+    command
+        .args(["push", "--progress", &remote, "--tags"])
+        .current_dir(repo_path);
+    run_push(command, &options, &OperationControl::none())
+}
+
+pub fn create_tag(repo_path: &Path, name: &str, commit: &str, message: Option<&str>) -> Result<()> {
+    let output = if let Some(msg) = message {
+        // Annotated tag with message
+        Command::new("git")
+            .current_dir(repo_path)
+            .args(&["tag", "-a", name, commit, "-m", msg])
+            .output()
+            .context("Failed to execute git tag")?
+    } else {
+        // Lightweight tag
+        Command::new("git")
+            .current_dir(repo_path)
+            .args(&["tag", name, commit])
+            .output()
+            .context("Failed to execute git tag")?
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git tag failed: {}", stderr);
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Git Diff for Compare with Local
+// ============================================================================
+pub fn get_commit_diff(repo_path: &Path, commit: &str) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["diff", commit, "HEAD"])
+        .output()
+        .context("Failed to execute git diff")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git diff failed: {}", stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn is_git_repo(repo_path: &Path) -> bool {
+    repo_path.join(".git").exists()
+}
+
+/// The repository's git directory (`.git`, or `.git/worktrees/<name>` for linked worktrees).
+pub fn get_git_dir(repo_path: &Path) -> Result<PathBuf> {
+    git_dir_path(repo_path, ".")
+}
+
+/// The git directory shared by the main working tree and all linked worktrees.
+pub fn get_common_git_dir(repo_path: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rev-parse", "--git-common-dir"])
+        .output()
+        .context("Failed to execute git rev-parse --git-common-dir")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git rev-parse failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        repo_path.join(path)
+    })
+}
+
+// ============================================================================
+// Index Lock
+// ============================================================================
+
+/// Minimum age before an `index.lock` may be removed without `force`. Git holds the lock
+/// only while it writes the index, so an older lock with no git process around is stale.
+const STALE_INDEX_LOCK_AGE: Duration = Duration::from_secs(5);
+
+/// An `index.lock` file present in the git directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexLock {
+    pub path: String,
+    /// Seconds since the lock file was last modified
+    pub age_secs: u64,
+    /// Whether a git process is running in this repository (it may own the lock). Where
+    /// processes can't be told apart by repository, any git process on the machine counts.
+    pub git_process_running: bool,
+}
+
+/// Whether a git error was caused by another process holding `index.lock`.
+pub fn is_index_lock_error(message: &str) -> bool {
+    message.contains("index.lock")
+}
+
+pub fn get_index_lock(repo_path: &Path) -> Result<Option<IndexLock>> {
+    let path = git_dir_path(repo_path, "index.lock")?;
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        return Ok(None);
+    };
+
+    let age_secs = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .map(|age| age.as_secs())
+        .unwrap_or(0);
+
+    Ok(Some(IndexLock {
+        path: path.to_string_lossy().to_string(),
+        age_secs,
+        git_process_running: is_git_process_running(repo_path),
+    }))
+}
+
+/// Removes a leftover `index.lock`. Without `force` this refuses while the lock is recent or
+/// any git process is running. Returns false if there was no lock.
+pub fn remove_stale_index_lock(repo_path: &Path, force: bool) -> Result<bool> {
+    let Some(lock) = get_index_lock(repo_path)? else {
+        return Ok(false);
+    };
+
+    if !force {
+        if lock.git_process_running {
+            if GIT_PROCESS_CHECK_PER_REPO {
+                anyhow::bail!(
+                    "A git process is still running in this repository and may own the index lock"
+                );
+            }
+            anyhow::bail!(
+                "A git process is running on this computer (it can't be told which repository it works on) and may own the index lock"
+            );
+        }
+        if lock.age_secs < STALE_INDEX_LOCK_AGE.as_secs() {
+            anyhow::bail!("The index lock was just created; it is probably still in use");
+        }
+    }
+
+    std::fs::remove_file(&lock.path).context("Failed to remove index.lock")?;
+    Ok(true)
+}
+
+/// Whether `is_git_process_running` can limit itself to one repository on this platform.
+const GIT_PROCESS_CHECK_PER_REPO: bool = cfg!(any(target_os = "linux", target_os = "macos"));
+
+/// Whether a git process is working in the repository, i.e. its working directory is inside
+/// the working tree (`git -C` changes into it too). Other platforms fall back to any git
+/// process on the machine. Errs on the side of "yes" when it cannot tell.
+#[cfg(target_os = "linux")]
+fn is_git_process_running(repo_path: &Path) -> bool {
+    let Ok(repo_path) = repo_path.canonicalize() else {
+        return true;
+    };
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return true;
+    };
+
+    processes.filter_map(|entry| entry.ok()).any(|entry| {
+        let process = entry.path();
+        let is_git = std::fs::read_to_string(process.join("comm"))
+            .is_ok_and(|command| command.trim() == "git");
+        // Another user's process hides its working directory
+        is_git
+            && std::fs::read_link(process.join("cwd"))
+                .map_or(true, |cwd| cwd.starts_with(&repo_path))
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn is_git_process_running(repo_path: &Path) -> bool {
+    let Ok(repo_path) = repo_path.canonicalize() else {
+        return true;
+    };
+    // Working directories of processes whose name starts with "git", as "n<path>" lines
+    let Ok(output) = Command::new("lsof")
+        .args(["-a", "-c", "git", "-d", "cwd", "-Fn"])
+        .output()
+    else {
+        return true;
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix('n'))
+        .any(|cwd| Path::new(cwd).starts_with(&repo_path))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn is_git_process_running(_repo_path: &Path) -> bool {
+    #[cfg(unix)]
+    let output = Command::new("pgrep").args(["-x", "git"]).output();
+    #[cfg(windows)]
+    let output = Command::new("tasklist")
+        .args(["/FI", "IMAGENAME eq git.exe", "/NH"])
+        .output();
+
+    match output {
+        #[cfg(unix)]
+        Ok(output) => output.status.success(),
+        #[cfg(windows)]
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains("git.exe"),
+        Err(_) => true,
+    }
+}
+
+/// Which of `paths` (relative to the repository root) are ignored by .gitignore rules.
+pub fn get_ignored_paths(repo_path: &Path, paths: &[String]) -> Result<HashSet<String>> {
+    if paths.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let mut child = Command::new("git")
+        .current_dir(repo_path)
+        .args(["check-ignore", "-z", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git check-ignore")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        for path in paths {
+            stdin
+                .write_all(path.as_bytes())
+                .and_then(|_| stdin.write_all(b"\0"))
+                .context("Failed to write paths to git check-ignore")?;
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for git check-ignore")?;
+
+    // Exit code 1 means none of the paths is ignored
+    if !output.status.success() && output.status.code() != Some(1) {
+        anyhow::bail!(
+            "Git check-ignore failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn repo_has_commits(repo_path: &Path) -> Result<bool> {
+    let output = Command::new("git")
+        .args(&["rev-parse", "--verify", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to check repository commits")?;
+
+    Ok(output.status.success())
+}
+
+pub fn init_repository(options: &InitRepoOptions) -> Result<InitRepoResult> {
+    let repo_path = PathBuf::from(&options.path);
+    if !repo_path.exists() {
+        anyhow::bail!("Repository path does not exist");
+    }
+    if !repo_path.is_dir() {
+        anyhow::bail!("Repository path is not a directory");
+    }
+    if is_git_repo(&repo_path) {
+        anyhow::bail!("Repository already initialized");
+    }
+
+    let repo_name = options.name.trim();
+    if repo_name.is_empty() {
+        anyhow::bail!("Repository name is required");
+    }
+
+    let branch = options.default_branch.trim();
+    if branch.is_empty() {
+        anyhow::bail!("Default branch is required");
+    }
+
+    let init_output = Command::new("git")
+        .args(&["init", "--initial-branch", branch])
+        .current_dir(&repo_path)
+        .output()
+        .context("Failed to execute git init")?;
+
+    if !init_output.status.success() {
+        anyhow::bail!(
+            "Git init failed: {}",
+            String::from_utf8_lossy(&init_output.stderr)
+        );
+    }
+
+    let mut created_files = Vec::new();
+    let mut skipped_files = Vec::new();
+
+    if options.add_readme {
+        let readme_path = repo_path.join("README.md");
+        if readme_path.exists() {
+            skipped_files.push("README.md".to_string());
+        } else {
+            let mut content = format!("# {}", repo_name);
+            if let Some(description) = options
+                .description
+                .as_ref()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+            {
+                content.push_str("\n\n");
+                content.push_str(description);
+            }
+            content.push('\n');
+            std::fs::write(&readme_path, content).with_context(|| "Failed to write README.md")?;
+            created_files.push("README.md".to_string());
+        }
+    }
+
+    if let Some(template) = options.gitignore_template.as_ref() {
+        if let Some(content) = gitignore_template(template) {
+            let gitignore_path = repo_path.join(".gitignore");
+            if gitignore_path.exists() {
+                skipped_files.push(".gitignore".to_string());
+            } else {
+                std::fs::write(&gitignore_path, content)
+                    .with_context(|| "Failed to write .gitignore")?;
+                created_files.push(".gitignore".to_string());
+            }
+        }
+    }
+
+    if let Some(license_id) = options.license.as_ref() {
+        if let Some(content) = license_text(license_id) {
+            let license_path = repo_path.join("LICENSE");
+            if license_path.exists() {
+                skipped_files.push("LICENSE".to_string());
+            } else {
+                std::fs::write(&license_path, content)
+                    .with_context(|| "Failed to write LICENSE")?;
+                created_files.push("LICENSE".to_string());
+            }
+        }
+    }
+
+    if options.initial_commit {
+        let add_output = Command::new("git")
+            .args(&["add", "-A"])
+            .current_dir(&repo_path)
+            .output()
+            .context("Failed to stage initial files")?;
+
+        if !add_output.status.success() {
+            anyhow::bail!(
+                "Git add failed: {}",
+                String::from_utf8_lossy(&add_output.stderr)
+            );
+        }
+
+        let message = options
+            .commit_message
+            .as_deref()
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .unwrap_or("chore: init repo");
+
+        let commit_output = Command::new("git")
+            .args(&["commit", "--allow-empty", "-m", message])
+            .current_dir(&repo_path)
+            .output()
+            .context("Failed to commit initial files")?;
+
+        if !commit_output.status.success() {
+            anyhow::bail!(
+                "Git commit failed: {}",
+                String::from_utf8_lossy(&commit_output.stderr)
+            );
+        }
+    }
+
+    Ok(InitRepoResult {
+        created_files,
+        skipped_files,
+    })
+}
+
+pub fn publish_github_repo(options: &PublishRepoOptions) -> Result<PublishRepoResult> {
+    let repo_path = PathBuf::from(&options.path);
+    if !repo_path.exists() {
+        anyhow::bail!("Repository path does not exist");
+    }
+    if !repo_path.is_dir() {
+        anyhow::bail!("Repository path is not a directory");
+    }
+    if !is_git_repo(&repo_path) {
+        anyhow::bail!("Repository is not initialized");
+    }
+
+    let repo_name = options.name.trim();
+    if repo_name.is_empty() {
+        anyhow::bail!("Repository name is required");
+    }
+
+    if get_remote_origin_url(&repo_path)?.is_some() {
+        anyhow::bail!("Repository already has a remote origin");
+    }
+
+    if !repo_has_commits(&repo_path)? {
+        anyhow::bail!("Repository has no commits. Create one before publishing.");
+    }
+
+    let visibility_flag = match options.visibility.trim().to_lowercase().as_str() {
+        "private" => "--private",
+        _ => "--public",
+    };
+
+    let mut args = vec![
+        "repo",
+        "create",
+        repo_name,
+        visibility_flag,
+        "--source",
+        ".",
+        "--push",
+        "--confirm",
+    ];
+
+    if let Some(description) = options
+        .description
+        .as_ref()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+    {
+        args.push("--description");
+        args.push(description);
+    }
+
+    let output = Command::new("gh")
+        .args(&args)
+        .current_dir(&repo_path)
+        .output()
+        .context("Failed to execute gh repo create")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let message = if stderr.is_empty() { stdout } else { stderr };
+        anyhow::bail!("GitHub publish failed: {}", message);
+    }
+
+    let origin = get_remote_origin_url(&repo_path)?.unwrap_or_default();
+    let url = if origin.trim().is_empty() {
+        None
+    } else {
+        Some(origin)
+    };
+
+    Ok(PublishRepoResult { url })
+}
+
+fn gitignore_template(template: &str) -> Option<&'static str> {
+    match template.trim().to_lowercase().as_str() {
+        "none" | "" => None,
+        "node" => Some(
+            r#"node_modules/
+dist/
+.env
+npm-debug.log*
+yarn-debug.log*
+yarn-error.log*
+"#,
+        ),
+        "python" => Some(
+            r#"__pycache__/
+*.py[cod]
+.venv/
+.env
+"#,
+        ),
+        "rust" => Some(
+            r#"target/
+"#,
+        ),
+        "go" => Some(
+            r#"bin/
+pkg/
+"#,
+        ),
+        "java" => Some(
+            r#"target/
+*.class
+*.jar
+"#,
+        ),
+        _ => None,
+    }
+}
+
+fn license_text(license_id: &str) -> Option<String> {
+    let author = get_git_user_name().unwrap_or_else(|| "Your Name".to_string());
+    let year = Utc::now().year();
+    match license_id.trim().to_lowercase().as_str() {
+        "none" | "" => None,
+        "mit" => Some(format!(
+            r#"MIT License
+
+Copyright (c) {year} {author}
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+"#
+        )),
+        "apache-2.0" => Some(format!(
+            r#"Apache License
+Version 2.0, January 2004
+http://www.apache.org/licenses/
+
+Copyright {year} {author}
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+"#
+        )),
+        _ => None,
+    }
+}
+
+fn get_git_user_name() -> Option<String> {
+    let output = Command::new("git")
+        .args(&["config", "--global", "user.name"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn parse_shortstat_line(line: &str) -> (usize, usize, usize) {
+    let mut files_changed = 0usize;
+    let mut insertions = 0usize;
+    let mut deletions = 0usize;
+
+    for part in line.split(',') {
+        let part = part.trim();
+        let Some(first_token) = part.split_whitespace().next() else {
+            continue;
+        };
+        let Ok(value) = first_token.parse::<usize>() else {
+            continue;
+        };
+
+        if part.contains("file changed") || part.contains("files changed") {
+            files_changed = value;
+        } else if part.contains("insertion") {
+            insertions = value;
+        } else if part.contains("deletion") {
+            deletions = value;
+        }
+    }
+
+    (files_changed, insertions, deletions)
+}
+
+fn is_merge_in_progress(repo_path: &Path) -> bool {
+    git_dir_path(repo_path, "MERGE_HEAD")
+        .map(|path| path.exists())
+        .unwrap_or_else(|_| repo_path.join(".git").join("MERGE_HEAD").exists())
+}
+
+pub fn check_merge_in_progress(repo_path: &Path) -> Result<(bool, Vec<String>)> {
+    if !is_merge_in_progress(repo_path) {
+        return Ok((false, Vec::new()));
+    }
+
+    let conflicts = get_conflict_files(repo_path)?;
+    Ok((true, conflicts))
+}
+
+pub fn get_conflict_files(repo_path: &Path) -> Result<Vec<String>> {
+    if get_operation_in_progress(repo_path)?.is_none() {
+        return Ok(Vec::new());
+    }
+
+    list_unmerged_files(repo_path)
+}
+
+/// Lists paths with unmerged index entries, whatever operation produced them.
+fn list_unmerged_files(repo_path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["ls-files", "-u", "-z"])
+        .output()
+        .context("Failed to list conflicted files")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git ls-files -u failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Format: "<mode> <object> <stage>\t<path>\0"
+    let mut files = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter_map(|entry| entry.split_once('\t'))
+        .map(|(_, path)| path.to_string())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<String>>();
+
+    files.sort();
+
+    Ok(files)
+}
+
+pub fn parse_conflict_file(repo_path: &Path, file_path: &str) -> Result<ConflictFile> {
+    let (base, ours, theirs) = get_conflict_sides(repo_path, file_path)?;
+    let kind = conflict_kind(&base, &ours, &theirs);
+
+    let full_path = repo_path.join(file_path);
+    let bytes = match std::fs::read(&full_path) {
+        Ok(bytes) => bytes,
+        // modify/delete conflicts may leave nothing in the working tree
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && kind.is_some() => Vec::new(),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {file_path}")),
+    };
+    let content = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => {
+            return Ok(ConflictFile {
+                path: file_path.to_string(),
+                conflicts: Vec::new(),
+                content: String::new(),
+                is_binary: true,
+                kind,
+                base,
+                ours,
+                theirs,
+            });
+        }
+    };
+
+    let mut conflicts = parse_conflict_hunks(&content);
+
+    // Plain "merge" style markers carry no base: rebuild it from the index stages
+    if conflicts.iter().any(|hunk| hunk.base_content.is_none()) {
+        if let Some(bases) = conflict_bases_from_index(repo_path, file_path) {
+            if bases.len() == conflicts.len() {
+                for (hunk, base) in conflicts.iter_mut().zip(bases) {
+                    if hunk.base_content.is_none() {
+                        hunk.base_content = base.base_content;
+                        hunk.base_label = base.base_label;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ConflictFile {
+        path: file_path.to_string(),
+        conflicts,
+        content,
+        is_binary: ours.is_binary || theirs.is_binary,
+        kind,
+        base,
+        ours,
+        theirs,
+    })
+}
+
+/// Reads the base/ours/theirs index stages of a conflicted path.
+fn get_conflict_sides(
+    repo_path: &Path,
+    file_path: &str,
+) -> Result<(ConflictSide, ConflictSide, ConflictSide)> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["ls-files", "-u", "-z", "--", file_path])
+        .output()
+        .context("Failed to list conflict stages")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git ls-files -u failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let mut sides: [ConflictSide; 3] = Default::default();
+    for side in sides.iter_mut() {
+        side.deleted = true;
+    }
+
+    // Format: "<mode> <object> <stage>\t<path>\0"
+    for entry in String::from_utf8_lossy(&output.stdout).split('\0') {
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        if path != file_path {
+            continue;
+        }
+        let fields: Vec<&str> = info.split_whitespace().collect();
+        let [mode, blob, stage] = fields[..] else {
+            continue;
+        };
+        let Some(side) = stage
+            .parse::<usize>()
+            .ok()
+            .and_then(|stage| stage.checked_sub(1))
+            .and_then(|index| sides.get_mut(index))
+        else {
+            continue;
+        };
+
+        side.deleted = false;
+        side.mode = Some(mode.to_string());
+        side.blob = Some(blob.to_string());
+        side.size = blob_size(repo_path, blob);
+        side.is_binary = blob_is_binary(repo_path, blob);
+    }
+
+    let [mut base, mut ours, mut theirs] = sides;
+    if let Some((base_ref, ours_ref, theirs_ref)) = conflict_refs(repo_path) {
+        let ours_renames = list_renames(repo_path, &base_ref, &ours_ref);
+        let theirs_renames = list_renames(repo_path, &base_ref, &theirs_ref);
+
+        let renamed_from = |renames: &[(String, String)]| {
+            renames
+                .iter()
+                .find(|(_, new)| new == file_path)
+                .map(|(old, _)| old.clone())
+        };
+        ours.renamed_from = renamed_from(&ours_renames);
+        theirs.renamed_from = renamed_from(&theirs_renames);
+
+        let base_path = ours
+            .renamed_from
+            .clone()
+            .or_else(|| theirs.renamed_from.clone())
+            .unwrap_or_else(|| file_path.to_string());
+        let renamed_to = |renames: &[(String, String)]| {
+            renames
+                .iter()
+                .find(|(old, new)| *old == base_path && new != file_path)
+                .map(|(_, new)| new.clone())
+        };
+        ours.renamed_to = renamed_to(&ours_renames);
+        theirs.renamed_to = renamed_to(&theirs_renames);
+        base.renamed_to = None;
+    }
+
+    Ok((base, ours, theirs))
+}
+
+fn conflict_kind(
+    base: &ConflictSide,
+    ours: &ConflictSide,
+    theirs: &ConflictSide,
+) -> Option<ConflictKind> {
+    match (!base.deleted, !ours.deleted, !theirs.deleted) {
+        (true, true, true) => Some(ConflictKind::BothModified),
+        (false, true, true) => Some(ConflictKind::BothAdded),
+        (true, false, false) => Some(ConflictKind::BothDeleted),
+        (true, false, true) => Some(ConflictKind::DeletedByUs),
+        (true, true, false) => Some(ConflictKind::DeletedByThem),
+        (false, true, false) => Some(ConflictKind::AddedByUs),
+        (false, false, true) => Some(ConflictKind::AddedByThem),
+        (false, false, false) => None,
+    }
+}
+
+/// (base, ours, theirs) commits of the operation that produced the conflicts,
+/// used to detect renames on each side.
+fn conflict_refs(repo_path: &Path) -> Option<(String, String, String)> {
+    let resolve = |rev: &str| -> Option<String> {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["rev-parse", "--verify", "--quiet", rev])
+            .output()
+            .ok()?;
+        let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !hash.is_empty()).then_some(hash)
+    };
+
+    let ours = resolve("HEAD")?;
+    let operation = get_operation_in_progress(repo_path).ok()??;
+    let (base, theirs) = match operation.kind {
+        OperationKind::Merge => {
+            let theirs = resolve("MERGE_HEAD")?;
+            let output = Command::new("git")
+                .current_dir(repo_path)
+                .args(["merge-base", &ours, &theirs])
+                .output()
+                .ok()?;
+            let base = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            (output.status.success() && !base.is_empty()).then_some((base, theirs))?
+        }
+        OperationKind::CherryPick => (resolve("CHERRY_PICK_HEAD^")?, resolve("CHERRY_PICK_HEAD")?),
+        OperationKind::Revert => (resolve("REVERT_HEAD")?, resolve("REVERT_HEAD^")?),
+        OperationKind::Rebase => (resolve("REBASE_HEAD^")?, resolve("REBASE_HEAD")?),
+        _ => return None,
+    };
+
+    Some((base, ours, theirs))
+}
+
+/// (old, new) pairs of files renamed between two commits.
+fn list_renames(repo_path: &Path, from: &str, to: &str) -> Vec<(String, String)> {
+    let Ok(output) = Command::new("git")
+        .current_dir(repo_path)
+        .args([
+            "diff",
+            "--name-status",
+            "-M",
+            "-z",
+            "--diff-filter=R",
+            from,
+            to,
+        ])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    // Format: "R<score>\0<old>\0<new>\0"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    fields
+        .chunks(3)
+        .filter(|chunk| chunk.len() == 3 && chunk[0].starts_with('R'))
+        .map(|chunk| (chunk[1].to_string(), chunk[2].to_string()))
+        .collect()
+}
+
+fn blob_size(repo_path: &Path, blob: &str) -> Option<u64> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["cat-file", "-s", blob])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Same heuristic as git: a NUL byte in the first 8000 bytes means binary.
+fn blob_is_binary(repo_path: &Path, blob: &str) -> bool {
+    Command::new("git")
+        .current_dir(repo_path)
+        .args(["cat-file", "blob", blob])
+        .output()
+        .map(|output| output.stdout.iter().take(8000).any(|byte| *byte == 0))
+        .unwrap_or(false)
+}
+
+/// Resolves a conflicted path by taking one side as a whole (works for binary files).
+/// If that side deleted the file, the resolution is the deletion.
+pub fn resolve_conflict_with_side(
+    repo_path: &Path,
+    file_path: &str,
+    side: ConflictResolutionSide,
+) -> Result<()> {
+    let (_, ours, theirs) = get_conflict_sides(repo_path, file_path)?;
+    let (chosen, flag) = match side {
+        ConflictResolutionSide::Ours => (ours, "--ours"),
+        ConflictResolutionSide::Theirs => (theirs, "--theirs"),
+    };
+
+    if chosen.deleted {
+        return resolve_conflict_as_deleted(repo_path, file_path);
+    }
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["checkout", flag, "--", file_path])
+        .output()
+        .context("Failed to execute git checkout for conflict side")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git checkout {} failed: {}",
+            flag,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["add", "--", file_path])
+        .output()
+        .context("Failed to execute git add for resolved file")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git add failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Resolves a conflicted path by deleting it (e.g. accepting a modify/delete conflict's deletion).
+pub fn resolve_conflict_as_deleted(repo_path: &Path, file_path: &str) -> Result<()> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rm", "--force", "--quiet", "--", file_path])
+        .output()
+        .context("Failed to execute git rm for conflicted file")?;
+
+    if !output.status.success() {
+        anyhow::bail!("Git rm failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(())
+}
+
+/// Parses conflict markers, in "merge" (ours/theirs) or "diff3"/"zdiff3" (ours/base/theirs) style.
+fn parse_conflict_hunks(content: &str) -> Vec<ConflictHunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut conflicts = Vec::new();
+    let mut current_hunk_id = 0;
+    let mut in_ours = false;
+    let mut in_base = false;
+    let mut in_theirs = false;
+    let mut ours_content = String::new();
+    let mut base_content: Option<String> = None;
+    let mut theirs_content = String::new();
+    let mut ours_label = String::new();
+    let mut base_label: Option<String> = None;
+    #[allow(unused_assignments)]
+    let mut theirs_label = String::new();
+    let mut start_line = 0usize;
+    let mut conflict_start_index: Option<usize> = None;
+
+    for (line_num, line) in lines.iter().enumerate() {
+        let current_line = line_num + 1;
+
+        if line.starts_with("<<<<<<<") {
+            in_ours = true;
+            in_base = false;
+            in_theirs = false;
+            start_line = current_line;
+            conflict_start_index = Some(line_num);
+            ours_label = line.trim_start_matches('<').trim().to_string();
+            continue;
+        }
+
+        if in_ours && line.starts_with("|||||||") {
+            in_ours = false;
+            in_base = true;
+            base_content = Some(String::new());
+            base_label = Some(line.trim_start_matches('|').trim().to_string());
+            continue;
+        }
+
+        if line.starts_with("=======") {
+            in_ours = false;
+            in_base = false;
+            in_theirs = true;
+            continue;
+        }
+
+        if line.starts_with(">>>>>>>") {
+            theirs_label = line.trim_start_matches('>').trim().to_string();
+            let start_index = conflict_start_index.unwrap_or(line_num);
+            let context_before_start = start_index.saturating_sub(3);
+            let context_before = lines[context_before_start..start_index]
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>();
+            let context_after_start = line_num + 1;
+            let context_after_end = (context_after_start + 3).min(lines.len());
+            let context_after = lines[context_after_start..context_after_end]
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>();
+
+            conflicts.push(ConflictHunk {
+                id: current_hunk_id,
+                ours_content: ours_content.clone(),
+                theirs_content: theirs_content.clone(),
+                base_content: base_content.take(),
+                ours_label: ours_label.clone(),
+                theirs_label: theirs_label.clone(),
+                base_label: base_label.take(),
+                start_line,
+                end_line: current_line,
+                context_before,
+                context_after,
+            });
+
+            current_hunk_id += 1;
+            in_ours = false;
+            in_base = false;
+            in_theirs = false;
+            ours_content.clear();
+            theirs_content.clear();
+            conflict_start_index = None;
+            continue;
+        }
+
+        if in_ours {
+            ours_content.push_str(line);
+            ours_content.push('\n');
+        } else if let (true, Some(base)) = (in_base, base_content.as_mut()) {
+            base.push_str(line);
+            base.push('\n');
+        } else if in_theirs {
+            theirs_content.push_str(line);
+            theirs_content.push('\n');
+        }
+    }
+
+    conflicts
+}
+
+/// Re-runs the three-way merge of index stages 1/2/3 in diff3 style to recover the
+/// base of every conflict hunk. Returns `None` when a stage is missing (e.g. add/add).
+fn conflict_bases_from_index(repo_path: &Path, file_path: &str) -> Option<Vec<ConflictHunk>> {
+    let read_stage = |stage: u8| -> Option<Vec<u8>> {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["show", &format!(":{stage}:{file_path}")])
+            .output()
+            .ok()?;
+        output.status.success().then_some(output.stdout)
+    };
+
+    let base = read_stage(1)?;
+    let ours = read_stage(2)?;
+    let theirs = read_stage(3)?;
+
+    // Unique per call, so concurrent reads (other tabs, other files) don't share stage files
+    static NEXT_WORK_DIR: AtomicUsize = AtomicUsize::new(0);
+    let work_dir = git_dir_path(
+        repo_path,
+        &format!(
+            "everydaygit-conflict-{}-{}",
+            std::process::id(),
+            NEXT_WORK_DIR.fetch_add(1, Ordering::Relaxed)
+        ),
+    )
+    .ok()?;
+    std::fs::create_dir_all(&work_dir).ok()?;
+    let paths = [
+        work_dir.join("ours"),
+        work_dir.join("base"),
+        work_dir.join("theirs"),
+    ];
+
+    let merged = (|| {
+        for (path, bytes) in paths.iter().zip([&ours, &base, &theirs]) {
+            std::fs::write(path, bytes).ok()?;
+        }
+
+        // Exit code is the number of conflicts; only negative codes (signals) are errors
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["merge-file", "-p", "--diff3"])
+            .args(["-L", "ours", "-L", "base", "-L", "theirs"])
+            .args(&paths)
+            .output()
+            .ok()?;
+        output.status.code()?;
+        String::from_utf8(output.stdout).ok()
+    })();
+
+    let _ = std::fs::remove_dir_all(&work_dir);
+
+    Some(parse_conflict_hunks(&merged?))
+}
+
+/// Re-creates the conflict markers of a file with the given style ("merge", "diff3"
+/// or "zdiff3"), discarding any manual edits to it.
+pub fn checkout_conflict_style(
+    repo_path: &Path,
+    file_path: &str,
+    style: &str,
+) -> Result<ConflictFile> {
+    let style = match style.trim().to_lowercase().as_str() {
+        "merge" => "merge",
+        "diff3" => "diff3",
+        "zdiff3" => "zdiff3",
+        other => anyhow::bail!("Unknown conflict style: {}", other),
+    };
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["checkout", &format!("--conflict={style}"), "--", file_path])
+        .output()
+        .context("Failed to execute git checkout --conflict")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git checkout --conflict failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    parse_conflict_file(repo_path, file_path)
+}
+
+// ============================================================================
+// Lockfile Conflicts
+// ============================================================================
+
+fn find_lockfile_rule<'a>(rules: &'a [LockfileRule], file_path: &str) -> Option<&'a LockfileRule> {
+    let file_name = file_path.rsplit('/').next().unwrap_or(file_path);
+    rules.iter().find(|rule| {
+        let pattern = rule.pattern.trim();
+        if pattern.contains('/') {
+            pattern.trim_start_matches("./") == file_path
+        } else {
+            pattern == file_name
+        }
+    })
+}
+
+/// Conflicted files that match one of the lockfile rules.
+pub fn get_lockfile_conflicts(
+    repo_path: &Path,
+    rules: &[LockfileRule],
+) -> Result<Vec<LockfileConflict>> {
+    Ok(get_conflict_files(repo_path)?
+        .into_iter()
+        .filter_map(|path| {
+            find_lockfile_rule(rules, &path).map(|rule| LockfileConflict {
+                rule: rule.clone(),
+                path,
+            })
+        })
+        .collect())
+}
+
+/// Checks out the configured side of a conflicted lockfile and runs its regeneration
+/// command. The path is left unmerged so the outcome can be reviewed before staging
+/// (with `stage_file` or `resolve_conflict_with_side`).
+pub fn resolve_lockfile_conflict(
+    repo_path: &Path,
+    file_path: &str,
+    rules: &[LockfileRule],
+) -> Result<LockfileResolution> {
+    let rule = find_lockfile_rule(rules, file_path)
+        .ok_or_else(|| anyhow!("No lockfile rule matches {file_path}"))?;
+
+    let (_, ours, theirs) = get_conflict_sides(repo_path, file_path)?;
+    let (chosen, flag) = match rule.side {
+        ConflictResolutionSide::Ours => (ours, "--ours"),
+        ConflictResolutionSide::Theirs => (theirs, "--theirs"),
+    };
+    if chosen.deleted {
+        anyhow::bail!("{file_path} was deleted on the chosen side; resolve it manually");
+    }
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["checkout", flag, "--", file_path])
+        .output()
+        .context("Failed to execute git checkout for lockfile")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git checkout {} failed: {}",
+            flag,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let command = rule
+        .command
+        .as_deref()
+        .map(str::trim)
+        .filter(|command| !command.is_empty());
+    let Some(command) = command else {
+        return Ok(LockfileResolution {
+            path: file_path.to_string(),
+            side: rule.side,
+            command: None,
+            success: true,
+            exit_code: None,
+            output: String::new(),
+            changed_files: Vec::new(),
+        });
+    };
+
+    let changed_before = changed_worktree_files(repo_path);
+    let work_dir = match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => repo_path.join(parent),
+        _ => repo_path.to_path_buf(),
+    };
+
+    let output = shell_command(command)
+        .current_dir(&work_dir)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run lockfile command: {command}"))?;
+
+    let mut combined = String::from_utf8_lossy(&output.stdout).to_string();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    let changed_files = changed_worktree_files(repo_path)
+        .into_iter()
+        .filter(|path| path != file_path && !changed_before.contains(path))
+        .collect();
+
+    Ok(LockfileResolution {
+        path: file_path.to_string(),
+        side: rule.side,
+        command: Some(command.to_string()),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        output: clean_terminal_output(&combined),
+        changed_files,
+    })
+}
+
+fn changed_worktree_files(repo_path: &Path) -> HashSet<String> {
+    let Ok(output) = Command::new("git")
+        .current_dir(repo_path)
+        .args(["status", "--porcelain", "-z", "--untracked-files=normal"])
+        .output()
+    else {
+        return HashSet::new();
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut entries = stdout.split('\0');
+    let mut files = HashSet::new();
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        if entry.starts_with('R') || entry.starts_with('C') {
+            // Rename/copy entries are followed by the original path
+            entries.next();
+        }
+        files.insert(entry[3..].to_string());
+    }
+    files
+}
+
+pub fn resolve_conflict_file(
+    repo_path: &Path,
+    file_path: &str,
+    resolved_content: &str,
+) -> Result<()> {
+    let full_path = repo_path.join(file_path);
+
+    std::fs::write(&full_path, resolved_content)
+        .with_context(|| format!("Failed to write resolved content to {file_path}"))?;
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["add", file_path])
+        .output()
+        .context("Failed to execute git add for resolved file")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git add failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+pub fn complete_merge(repo_path: &Path, message: Option<&str>) -> Result<String> {
+    if !is_merge_in_progress(repo_path) {
+        // Conflicts from a cherry-pick, revert, rebase or am are completed by continuing it
+        return match get_operation_in_progress(repo_path)? {
+            Some(operation) if operation.can_continue => {
+                let result = continue_operation(repo_path)?;
+                if !result.completed {
+                    if let Some(operation) = result.operation {
+                        if !operation.conflicts.is_empty() {
+                            return Err(anyhow!("Unresolved conflicts: {:?}", operation.conflicts));
+                        }
+                    }
+                }
+                Ok(result.summary)
+            }
+            _ => Err(anyhow!("No merge in progress")),
+        };
+    }
+
+    let conflicts = get_conflict_files(repo_path)?;
+    if !conflicts.is_empty() {
+        return Err(anyhow!("Unresolved conflicts: {:?}", conflicts));
+    }
+
+    let mut args = vec!["commit"];
+    if let Some(msg) = message {
+        args.push("-m");
+        args.push(msg);
+    } else {
+        args.push("--no-edit");
+    }
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute git commit for merge")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git commit failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn merge_preview(repo_path: &Path, source: &str, target: Option<&str>) -> Result<MergePreview> {
+    let target_ref = target.unwrap_or("HEAD");
+
+    // 1. Verificar se pode fazer fast-forward
+    let ff_status = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["merge-base", "--is-ancestor", target_ref, source])
+        .output()
+        .context("Failed to check fast-forward")?;
+    let can_fast_forward = ff_status.status.success();
+
+    // 2. Obter estatísticas do diff entre as branches (sem fazer merge real)
+    // Isso funciona mesmo com working tree suja
+    let shortstat_output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&[
+            "diff",
+            "--shortstat",
+            &format!("{}..{}", target_ref, source),
+        ])
+        .output()
+        .context("Failed to get diff stats between branches")?;
+    let shortstat = String::from_utf8_lossy(&shortstat_output.stdout);
+    let (files_changed, insertions, deletions) = parse_shortstat_line(shortstat.trim());
+
+    // 3. Detectar conflitos potenciais usando merge-tree (não modifica working tree)
+    // Primeiro, encontrar o merge-base
+    let merge_base_output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["merge-base", target_ref, source])
+        .output()
+        .context("Failed to find merge base")?;
+
+    let mut conflicts = Vec::new();
+
+    if merge_base_output.status.success() {
+        let merge_base = String::from_utf8_lossy(&merge_base_output.stdout)
+            .trim()
+            .to_string();
+
+        // Usar merge-tree para simular merge e detectar conflitos
+        let merge_tree_output = Command::new("git")
+            .current_dir(repo_path)
+            .args(&["merge-tree", &merge_base, target_ref, source])
+            .output()
+            .context("Failed to simulate merge")?;
+
+        let merge_tree_result = String::from_utf8_lossy(&merge_tree_output.stdout);
+
+        // Parsear conflitos do output do merge-tree
+        // Formato: linhas com "+" no início após marcadores de conflito
+        let mut in_conflict = false;
+        let mut current_file = String::new();
+
+        for line in merge_tree_result.lines() {
+            if line.starts_with("changed in both") {
+                in_conflict = true;
+            } else if line.starts_with("  base")
+                || line.starts_with("  our")
+                || line.starts_with("  their")
+            {
+                // Extrair nome do arquivo
+                if let Some(path) = line.split_whitespace().last() {
+                    current_file = path.to_string();
+                }
+            } else if in_conflict && line.contains("<<<<<<<") {
+                if !current_file.is_empty() && !conflicts.contains(&current_file) {
+                    conflicts.push(current_file.clone());
+                }
+            } else if line.is_empty() {
+                in_conflict = false;
+            }
+        }
+    }
+
+    Ok(MergePreview {
+        can_fast_forward,
+        conflicts,
+        files_changed,
+        insertions,
+        deletions,
+    })
+}
+
+pub fn merge_branch(repo_path: &Path, source: &str, message: Option<&str>) -> Result<MergeResult> {
+    let ff_status = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["merge-base", "--is-ancestor", "HEAD", source])
+        .output()
+        .context("Failed to check fast-forward")?;
+    let can_fast_forward = ff_status.status.success();
+
+    let mut args = vec!["merge", "--no-commit"];
+    if let Some(msg) = message {
+        args.push("-m");
+        args.push(msg);
+    }
+    args.push(source);
+
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute git merge")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let summary = if !stdout.is_empty() {
+        stdout
+    } else {
+        stderr.clone()
+    };
 
-    let output = Command::new("gh")
-        .args(&args)
-        .current_dir(&repo_path)
-        .output()
-        .context("Failed to execute gh repo create")?;
+    // Com --no-commit, verificamos se há merge em progresso
+    let merge_started = is_merge_in_progress(repo_path);
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let message = if stderr.is_empty() { stdout } else { stderr };
-        anyhow::bail!("GitHub publish failed: {}", message);
+        // Merge falhou - provavelmente há conflitos
+        if merge_started {
+            let conflicts = get_conflict_files(repo_path)?;
+            return Ok(MergeResult {
+                fast_forward: can_fast_forward,
+                summary,
+                conflicts,
+                pending_stash: None,
+            });
+        }
+
+        return Err(anyhow!("Git merge failed: {}", stderr));
     }
 
-    let origin = get_remote_origin_url(&repo_path)?.unwrap_or_default();
-    let url = if origin.trim().is_empty() {
-        None
+    // Merge bem-sucedido com --no-commit
+    // Verifica se há conflitos pendentes mesmo assim
+    let conflicts = if merge_started {
+        get_conflict_files(repo_path)?
     } else {
-        Some(origin)
+        Vec::new()
     };
 
-    Ok(PublishRepoResult { url })
+    Ok(MergeResult {
+        fast_forward: can_fast_forward,
+        summary,
+        conflicts,
+        pending_stash: None,
+    })
 }
 
-fn gitignore_template(template: &str) -> Option<&'static str> {
-    match template.trim().to_lowercase().as_str() {
-        "none" | "" => None,
-        "node" => Some(
-            r#"node_modules/
-dist/
-.env
-npm-debug.log*
-yarn-debug.log*
-yarn-error.log*
-"#,
-        ),
-        "python" => Some(
-            r#"__pycache__/
-*.py[cod]
-.venv/
-.env
-"#,
-        ),
-        "rust" => Some(
-            r#"target/
-"#,
-        ),
-        "go" => Some(
-            r#"bin/
-pkg/
-"#,
-        ),
-        "java" => Some(
-            r#"target/
-*.class
-*.jar
-"#,
-        ),
-        _ => None,
-    }
-}
+pub fn compare_branches(repo_path: &Path, base: &str, compare: &str) -> Result<BranchComparison> {
+    let rev_list = Command::new("git")
+        .current_dir(repo_path)
+        .args(&[
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("{base}...{compare}"),
+        ])
+        .output()
+        .context("Failed to compare branches")?;
 
-fn license_text(license_id: &str) -> Option<String> {
-    let author = get_git_user_name().unwrap_or_else(|| "Your Name".to_string());
-    let year = Utc::now().year();
-    match license_id.trim().to_lowercase().as_str() {
-        "none" | "" => None,
-        "mit" => Some(format!(
-            r#"MIT License
+    if !rev_list.status.success() {
+        anyhow::bail!(
+            "Git rev-list failed: {}",
+            String::from_utf8_lossy(&rev_list.stderr)
+        );
+    }
 
-Copyright (c) {year} {author}
+    let mut ahead = 0usize;
+    let mut behind = 0usize;
+    let counts = String::from_utf8_lossy(&rev_list.stdout);
+    let parts: Vec<&str> = counts.split_whitespace().collect();
+    if parts.len() >= 2 {
+        behind = parts[0].parse::<usize>().unwrap_or(0);
+        ahead = parts[1].parse::<usize>().unwrap_or(0);
+    }
 
-Permission is hereby granted, free of charge, to any person obtaining a copy
-of this software and associated documentation files (the "Software"), to deal
-in the Software without restriction, including without limitation the rights
-to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
-copies of the Software, and to permit persons to whom the Software is
-furnished to do so, subject to the following conditions:
+    let log_output = Command::new("git")
+        .current_dir(repo_path)
+        .args([
+            "log",
+            "--decorate=full",
+            COMMIT_LOG_FORMAT,
+            &format!("{base}..{compare}"),
+        ])
+        .output()
+        .context("Failed to collect comparison log")?;
 
-The above copyright notice and this permission notice shall be included in all
-copies or substantial portions of the Software.
+    let commits = if log_output.status.success() {
+        parse_commit_log(&String::from_utf8_lossy(&log_output.stdout))
+    } else {
+        Vec::new()
+    };
 
-THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
-IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
-FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
-AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
-LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
-OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
-SOFTWARE.
-"#
-        )),
-        "apache-2.0" => Some(format!(
-            r#"Apache License
-Version 2.0, January 2004
-http://www.apache.org/licenses/
+    let diff_output = Command::new("git")
+        .current_dir(repo_path)
+        .args(&["diff", "--stat", &format!("{base}..{compare}")])
+        .output()
+        .context("Failed to compute diff summary")?;
 
-Copyright {year} {author}
+    let diff_summary = if diff_output.status.success() {
+        String::from_utf8_lossy(&diff_output.stdout)
+            .trim()
+            .to_string()
+    } else {
+        String::new()
+    };
 
-Licensed under the Apache License, Version 2.0 (the "License");
-you may not use this file except in compliance with the License.
-You may obtain a copy of the License at
+    Ok(BranchComparison {
+        ahead,
+        behind,
+        commits,
+        diff_summary,
+    })
+}
 
-    http://www.apache.org/licenses/LICENSE-2.0
+// ============================================================================
+// Git Stash
+// ============================================================================
 
-Unless required by applicable law or agreed to in writing, software
-distributed under the License is distributed on an "AS IS" BASIS,
-WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
-See the License for the specific language governing permissions and
-limitations under the License.
-"#
-        )),
-        _ => None,
-    }
-}
+const AUTO_STASH_PREFIX: &str = "EverydayGit auto-stash";
 
-fn get_git_user_name() -> Option<String> {
+pub fn get_stashes(repo_path: &Path) -> Result<Vec<StashEntry>> {
     let output = Command::new("git")
-        .args(&["config", "--global", "user.name"])
+        .current_dir(repo_path)
+        .args(["stash", "list", "--format=%gd%x1f%H%x1f%gs%x1f%ai%x1e"])
         .output()
-        .ok()?;
+        .context("Failed to execute git stash list")?;
+
     if !output.status.success() {
-        return None;
-    }
-    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if name.is_empty() {
-        None
-    } else {
-        Some(name)
+        anyhow::bail!(
+            "Git stash list failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
-}
 
-fn parse_shortstat_line(line: &str) -> (usize, usize, usize) {
-    let mut files_changed = 0usize;
-    let mut insertions = 0usize;
-    let mut deletions = 0usize;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut stashes = Vec::new();
 
-    for part in line.split(',') {
-        let part = part.trim();
-        let Some(first_token) = part.split_whitespace().next() else {
+    for record in stdout.split('\u{001e}') {
+        let record = record.trim_start_matches('\n');
+        if record.trim().is_empty() {
             continue;
-        };
-        let Ok(value) = first_token.parse::<usize>() else {
+        }
+
+        let parts: Vec<&str> = record.split('\u{001f}').collect();
+        if parts.len() < 4 {
             continue;
+        }
+
+        let reference = parts[0].trim().to_string();
+        let index = reference
+            .trim_start_matches("stash@{")
+            .trim_end_matches('}')
+            .parse()
+            .unwrap_or(stashes.len());
+
+        // Subject is "WIP on <branch>: <hash> <msg>" or "On <branch>: <msg>"
+        let subject = parts[2].trim();
+        let (branch, message) = match subject
+            .strip_prefix("WIP on ")
+            .or_else(|| subject.strip_prefix("On "))
+            .and_then(|rest| rest.split_once(": "))
+        {
+            Some((branch, message)) => (Some(branch.to_string()), message.to_string()),
+            None => (None, subject.to_string()),
         };
 
-        if part.contains("file changed") || part.contains("files changed") {
-            files_changed = value;
-        } else if part.contains("insertion") {
-            insertions = value;
-        } else if part.contains("deletion") {
-            deletions = value;
-        }
+        stashes.push(StashEntry {
+            index,
+            reference,
+            hash: parts[1].trim().to_string(),
+            message,
+            branch,
+            date: parts[3].trim().to_string(),
+        });
     }
 
-    (files_changed, insertions, deletions)
+    Ok(stashes)
 }
 
-fn is_merge_in_progress(repo_path: &Path) -> bool {
-    repo_path.join(".git").join("MERGE_HEAD").exists()
-}
+/// Creates a stash. Returns `false` when there was nothing to stash.
+pub fn create_stash(repo_path: &Path, options: &StashCreateOptions) -> Result<bool> {
+    let mut args = vec!["stash", "push"];
+    if let Some(message) = options
+        .message
+        .as_deref()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+    {
+        args.push("-m");
+        args.push(message);
+    }
+    if options.include_untracked {
+        args.push("--include-untracked");
+    }
+    if options.keep_index {
+        args.push("--keep-index");
+    }
+    if !options.paths.is_empty() {
+        args.push("--");
+        args.extend(options.paths.iter().map(|path| path.as_str()));
+    }
 
-pub fn check_merge_in_progress(repo_path: &Path) -> Result<(bool, Vec<String>)> {
-    if !is_merge_in_progress(repo_path) {
-        return Ok((false, Vec::new()));
+    let before = latest_stash_hash(repo_path)?;
+
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute git stash push")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git stash failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 
-    let conflicts = get_conflict_files(repo_path)?;
-    Ok((true, conflicts))
+    Ok(latest_stash_hash(repo_path)? != before)
 }
 
-pub fn get_conflict_files(repo_path: &Path) -> Result<Vec<String>> {
-    if !is_merge_in_progress(repo_path) {
-        return Ok(Vec::new());
-    }
+pub fn apply_stash(
+    repo_path: &Path,
+    index: usize,
+    restore_index: bool,
+) -> Result<StashApplyResult> {
+    run_stash_apply(repo_path, "apply", index, restore_index)
+}
+
+pub fn pop_stash(repo_path: &Path, index: usize, restore_index: bool) -> Result<StashApplyResult> {
+    run_stash_apply(repo_path, "pop", index, restore_index)
+}
+
+pub fn drop_stash(repo_path: &Path, index: usize) -> Result<()> {
+    let reference = stash_reference(repo_path, index)?;
 
     let output = Command::new("git")
         .current_dir(repo_path)
-        .args(&["ls-files", "-u"])
+        .args(["stash", "drop", &reference])
         .output()
-        .context("Failed to list conflicted files")?;
+        .context("Failed to execute git stash drop")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git ls-files -u failed: {}",
+            "Git stash drop failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    let mut files = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .filter_map(|line| line.split_whitespace().nth(3))
-        .map(|path| path.to_string())
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .collect::<Vec<String>>();
-
-    files.sort();
-
-    Ok(files)
+    Ok(())
 }
 
-pub fn parse_conflict_file(repo_path: &Path, file_path: &str) -> Result<ConflictFile> {
-    let full_path = repo_path.join(file_path);
-    let bytes = std::fs::read(&full_path).with_context(|| format!("Failed to read {file_path}"))?;
-    let content = match String::from_utf8(bytes) {
-        Ok(text) => text,
-        Err(_) => {
-            return Ok(ConflictFile {
-                path: file_path.to_string(),
-                conflicts: Vec::new(),
-                content: String::new(),
-                is_binary: true,
-            });
-        }
-    };
-
-    let lines: Vec<&str> = content.lines().collect();
-    let mut conflicts = Vec::new();
-    let mut current_hunk_id = 0;
-    let mut in_ours = false;
-    let mut in_theirs = false;
-    let mut ours_content = String::new();
-    let mut theirs_content = String::new();
-    let mut ours_label = String::new();
-    #[allow(unused_assignments)]
-    let mut theirs_label = String::new();
-    let mut start_line = 0usize;
-    let mut conflict_start_index: Option<usize> = None;
+/// Changes recorded in a stash, including its untracked files.
+pub fn get_stash_diff(repo_path: &Path, index: usize) -> Result<Vec<FileDiff>> {
+    let reference = stash_reference(repo_path, index)?;
+    run_structured_diff_command(
+        repo_path,
+        &["stash", "show"],
+        &["-p", "-M", "--include-untracked", &reference],
+    )
+}
 
-    for (line_num, line) in lines.iter().enumerate() {
-        let current_line = line_num + 1;
+fn run_stash_apply(
+    repo_path: &Path,
+    action: &str,
+    index: usize,
+    restore_index: bool,
+) -> Result<StashApplyResult> {
+    let reference = stash_reference(repo_path, index)?;
+    let mut args = vec!["stash", action];
+    if restore_index {
+        args.push("--index");
+    }
+    args.push(&reference);
 
-        if line.starts_with("<<<<<<<") {
-            in_ours = true;
-            in_theirs = false;
-            start_line = current_line;
-            conflict_start_index = Some(line_num);
-            ours_label = line.trim_start_matches('<').trim().to_string();
-            continue;
-        }
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(&args)
+        .output()
+        .with_context(|| format!("Failed to execute git stash {action}"))?;
 
-        if line.starts_with("=======") {
-            in_ours = false;
-            in_theirs = true;
-            continue;
-        }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let summary = if !stdout.is_empty() {
+        stdout
+    } else {
+        stderr.clone()
+    };
 
-        if line.starts_with(">>>>>>>") {
-            theirs_label = line.trim_start_matches('>').trim().to_string();
-            let start_index = conflict_start_index.unwrap_or(line_num);
-            let context_before_start = start_index.saturating_sub(3);
-            let context_before = lines[context_before_start..start_index]
-                .iter()
-                .map(|value| value.to_string())
-                .collect::<Vec<String>>();
-            let context_after_start = line_num + 1;
-            let context_after_end = (context_after_start + 3).min(lines.len());
-            let context_after = lines[context_after_start..context_after_end]
-                .iter()
-                .map(|value| value.to_string())
-                .collect::<Vec<String>>();
+    // A conflicting apply exits non-zero but leaves unmerged paths (and keeps the stash on pop)
+    let conflicts = list_unmerged_files(repo_path)?;
+    if !output.status.success() && conflicts.is_empty() {
+        anyhow::bail!("Git stash {} failed: {}", action, stderr);
+    }
 
-            conflicts.push(ConflictHunk {
-                id: current_hunk_id,
-                ours_content: ours_content.clone(),
-                theirs_content: theirs_content.clone(),
-                ours_label: ours_label.clone(),
-                theirs_label: theirs_label.clone(),
-                start_line,
-                end_line: current_line,
-                context_before,
-                context_after,
-            });
+    Ok(StashApplyResult { summary, conflicts })
+}
 
-            current_hunk_id += 1;
-            in_ours = false;
-            in_theirs = false;
-            ours_content.clear();
-            theirs_content.clear();
-            conflict_start_index = None;
-            continue;
-        }
+fn stash_reference(repo_path: &Path, index: usize) -> Result<String> {
+    get_stashes(repo_path)?
+        .into_iter()
+        .find(|stash| stash.index == index)
+        .map(|stash| stash.reference)
+        .ok_or_else(|| anyhow!("Stash stash@{{{}}} not found", index))
+}
 
-        if in_ours {
-            ours_content.push_str(line);
-            ours_content.push('\n');
-        } else if in_theirs {
-            theirs_content.push_str(line);
-            theirs_content.push('\n');
-        }
-    }
+fn latest_stash_hash(repo_path: &Path) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rev-parse", "--verify", "--quiet", "refs/stash"])
+        .output()
+        .context("Failed to resolve refs/stash")?;
 
-    Ok(ConflictFile {
-        path: file_path.to_string(),
-        conflicts,
-        content,
-        is_binary: false,
+    let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(if output.status.success() && !hash.is_empty() {
+        Some(hash)
+    } else {
+        None
     })
 }
 
-pub fn resolve_conflict_file(
-    repo_path: &Path,
-    file_path: &str,
-    resolved_content: &str,
-) -> Result<()> {
-    let full_path = repo_path.join(file_path);
-
-    std::fs::write(&full_path, resolved_content)
-        .with_context(|| format!("Failed to write resolved content to {file_path}"))?;
-
+fn has_local_changes(repo_path: &Path) -> Result<bool> {
     let output = Command::new("git")
         .current_dir(repo_path)
-        .args(&["add", file_path])
+        .args(["status", "--porcelain", "--untracked-files=normal"])
         .output()
-        .context("Failed to execute git add for resolved file")?;
+        .context("Failed to execute git status")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git add failed: {}",
+            "Git status failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    Ok(())
+    Ok(!output.stdout.is_empty())
 }
 
-pub fn complete_merge(repo_path: &Path, message: Option<&str>) -> Result<String> {
-    if !is_merge_in_progress(repo_path) {
-        return Err(anyhow!("No merge in progress"));
+/// Runs `run` with local changes (including untracked files) stashed away and
+/// restores them afterwards.
+///
+/// When `run` leaves a merge, rebase, cherry-pick or revert stopped the stash cannot
+/// be applied yet, so it is kept and its reference returned; apply it once the
+/// operation is finished.
+pub fn with_auto_stash<T>(
+    repo_path: &Path,
+    operation: &str,
+    run: impl FnOnce() -> Result<T>,
+) -> Result<(T, Option<String>)> {
+    if !has_local_changes(repo_path)? {
+        return run().map(|value| (value, None));
     }
 
-    let conflicts = get_conflict_files(repo_path)?;
-    if !conflicts.is_empty() {
-        return Err(anyhow!("Unresolved conflicts: {:?}", conflicts));
+    let message = format!("{AUTO_STASH_PREFIX} before {operation}");
+    let stashed = create_stash(
+        repo_path,
+        &StashCreateOptions {
+            message: Some(message),
+            include_untracked: true,
+            ..Default::default()
+        },
+    )?;
+    let stash_hash = match (stashed, latest_stash_hash(repo_path)?) {
+        (true, Some(hash)) => hash,
+        _ => return run().map(|value| (value, None)),
+    };
+
+    let result = run();
+    let stash_index = get_stashes(repo_path)?
+        .into_iter()
+        .find(|stash| stash.hash == stash_hash)
+        .map(|stash| stash.index)
+        .ok_or_else(|| anyhow!("Auto-stash {} disappeared", stash_hash))?;
+
+    // Popping into a stopped merge, rebase, cherry-pick or revert would mix the changes in
+    if get_operation_in_progress(repo_path)?.is_some() {
+        let reference = format!("stash@{{{stash_index}}}");
+        return match result {
+            Ok(value) => Ok((value, Some(reference))),
+            Err(err) => Err(anyhow!(
+                "{err}\nLocal changes were stashed as {reference}. Apply it after finishing the operation in progress."
+            )),
+        };
     }
 
-    let mut args = vec!["commit"];
-    if let Some(msg) = message {
-        args.push("-m");
-        args.push(msg);
-    } else {
-        args.push("--no-edit");
+    let restored = pop_stash(repo_path, stash_index, false);
+    match (result, restored) {
+        (Ok(value), Ok(outcome)) if outcome.conflicts.is_empty() => Ok((value, None)),
+        (Ok(_), Ok(outcome)) => Err(anyhow!(
+            "{operation} succeeded, but restoring local changes conflicted in: {}. The auto-stash was kept as stash@{{{stash_index}}}.",
+            outcome.conflicts.join(", ")
+        )),
+        (Ok(_), Err(err)) => Err(anyhow!(
+            "{operation} succeeded, but restoring local changes failed: {err}"
+        )),
+        (Err(err), Ok(outcome)) if outcome.conflicts.is_empty() => Err(err),
+        (Err(err), _) => Err(anyhow!(
+            "{err}\nRestoring local changes did not complete cleanly; check stash@{{{stash_index}}}."
+        )),
     }
+}
+
+// ============================================================================
+// Interactive Rebase
+// ============================================================================
 
+/// Directory (inside the git dir) holding the generated todo list and messages.
+const REBASE_WORK_DIR: &str = "everydaygit-rebase";
+
+/// Resolves a path inside the git directory (works for worktrees and `.git` files).
+fn git_dir_path(repo_path: &Path, name: &str) -> Result<PathBuf> {
     let output = Command::new("git")
         .current_dir(repo_path)
-        .args(&args)
+        .args(["rev-parse", "--git-path", name])
         .output()
-        .context("Failed to execute git commit for merge")?;
+        .context("Failed to execute git rev-parse --git-path")?;
 
     if !output.status.success() {
         anyhow::bail!(
-            "Git commit failed: {}",
+            "Git rev-parse failed: {}",
             String::from_utf8_lossy(&output.stderr)
         );
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        repo_path.join(path)
+    })
 }
 
-pub fn merge_preview(repo_path: &Path, source: &str, target: Option<&str>) -> Result<MergePreview> {
-    let target_ref = target.unwrap_or("HEAD");
+/// Keeps only the final state of lines rewritten with `\r` and drops "erase line" escapes.
+fn clean_terminal_output(output: &str) -> String {
+    output
+        .replace("\u{1b}[K", "")
+        .lines()
+        .filter_map(|line| line.split('\r').rev().find(|part| !part.trim().is_empty()))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
 
-    // 1. Verificar se pode fazer fast-forward
-    let ff_status = Command::new("git")
-        .current_dir(repo_path)
-        .args(&["merge-base", "--is-ancestor", target_ref, source])
-        .output()
-        .context("Failed to check fast-forward")?;
-    let can_fast_forward = ff_status.status.success();
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
 
-    // 2. Obter estatísticas do diff entre as branches (sem fazer merge real)
-    // Isso funciona mesmo com working tree suja
-    let shortstat_output = Command::new("git")
-        .current_dir(repo_path)
-        .args(&[
-            "diff",
-            "--shortstat",
-            &format!("{}..{}", target_ref, source),
-        ])
-        .output()
-        .context("Failed to get diff stats between branches")?;
-    let shortstat = String::from_utf8_lossy(&shortstat_output.stdout);
-    let (files_changed, insertions, deletions) = parse_shortstat_line(shortstat.trim());
+fn is_rebase_in_progress(repo_path: &Path) -> bool {
+    ["rebase-merge", "rebase-apply"].iter().any(|name| {
+        git_dir_path(repo_path, name)
+            .map(|path| path.exists())
+            .unwrap_or(false)
+    })
+}
 
-    // 3. Detectar conflitos potenciais usando merge-tree (não modifica working tree)
-    // Primeiro, encontrar o merge-base
-    let merge_base_output = Command::new("git")
+/// Commits in `base..HEAD` as a default (all `pick`) todo list, oldest first.
+pub fn get_rebase_todo(repo_path: &Path, base: Option<&str>) -> Result<Vec<RebaseTodoItem>> {
+    let range = match base {
+        Some(base) => format!("{base}..HEAD"),
+        None => "HEAD".to_string(),
+    };
+
+    let output = Command::new("git")
         .current_dir(repo_path)
-        .args(&["merge-base", target_ref, source])
+        .args([
+            "log",
+            "--reverse",
+            "--no-merges",
+            "--pretty=format:%H%x1f%s%x1e",
+            &range,
+        ])
         .output()
-        .context("Failed to find merge base")?;
-
-    let mut conflicts = Vec::new();
+        .context("Failed to execute git log")?;
 
-    if merge_base_output.status.success() {
-        let merge_base = String::from_utf8_lossy(&merge_base_output.stdout)
-            .trim()
-            .to_string();
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git log failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
 
-        // Usar merge-tree para simular merge e detectar conflitos
-        let merge_tree_output = Command::new("git")
-            .current_dir(repo_path)
-            .args(&["merge-tree", &merge_base, target_ref, source])
-            .output()
-            .context("Failed to simulate merge")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\u{001e}')
+        .filter_map(|record| record.trim().split_once('\u{001f}'))
+        .map(|(hash, subject)| RebaseTodoItem {
+            action: RebaseAction::Pick,
+            hash: hash.to_string(),
+            subject: subject.to_string(),
+            message: None,
+        })
+        .collect())
+}
 
-        let merge_tree_result = String::from_utf8_lossy(&merge_tree_output.stdout);
+/// Runs `git rebase -i` with `options.todo` as the todo list, without opening any editor.
+///
+/// Rewords and squash messages are applied with an `exec git commit --amend -F <file>`
+/// line right after the commit, so no message editor is needed either.
+pub fn start_interactive_rebase(
+    repo_path: &Path,
+    options: &InteractiveRebaseOptions,
+) -> Result<RebaseResult> {
+    if is_rebase_in_progress(repo_path) {
+        anyhow::bail!("A rebase is already in progress. Continue or abort it first.");
+    }
+    if is_merge_in_progress(repo_path) {
+        anyhow::bail!("Cannot rebase while a merge is in progress. Resolve conflicts first.");
+    }
+    if options.todo.is_empty() {
+        anyhow::bail!("The rebase todo list is empty");
+    }
+    if options
+        .todo
+        .iter()
+        .find(|item| item.action != RebaseAction::Drop)
+        .is_some_and(|item| matches!(item.action, RebaseAction::Squash | RebaseAction::Fixup))
+    {
+        anyhow::bail!("The first commit cannot be squashed or fixed up into a previous one");
+    }
 
-        // Parsear conflitos do output do merge-tree
-        // Formato: linhas com "+" no início após marcadores de conflito
-        let mut in_conflict = false;
-        let mut current_file = String::new();
+    let work_dir = git_dir_path(repo_path, REBASE_WORK_DIR)?;
+    if work_dir.exists() {
+        std::fs::remove_dir_all(&work_dir).context("Failed to clean rebase work directory")?;
+    }
+    std::fs::create_dir_all(&work_dir).context("Failed to create rebase work directory")?;
 
-        for line in merge_tree_result.lines() {
-            if line.starts_with("changed in both") {
-                in_conflict = true;
-            } else if line.starts_with("  base")
-                || line.starts_with("  our")
-                || line.starts_with("  their")
-            {
-                // Extrair nome do arquivo
-                if let Some(path) = line.split_whitespace().last() {
-                    current_file = path.to_string();
-                }
-            } else if in_conflict && line.contains("<<<<<<<") {
-                if !current_file.is_empty() && !conflicts.contains(&current_file) {
-                    conflicts.push(current_file.clone());
-                }
-            } else if line.is_empty() {
-                in_conflict = false;
-            }
+    let mut todo = String::new();
+    for (index, item) in options.todo.iter().enumerate() {
+        let hash = item.hash.trim();
+        if hash.is_empty() || hash.contains(char::is_whitespace) {
+            anyhow::bail!("Invalid commit hash in rebase todo: {:?}", item.hash);
+        }
+
+        let (command, new_message) = match item.action {
+            RebaseAction::Pick => ("pick", None),
+            // Reword through pick + amend so git does not ask for the message
+            RebaseAction::Reword => ("pick", item.message.as_deref()),
+            RebaseAction::Edit => ("edit", None),
+            RebaseAction::Squash => ("squash", item.message.as_deref()),
+            RebaseAction::Fixup => ("fixup", None),
+            RebaseAction::Drop => ("drop", None),
+        };
+        // The subject is only a label for git status; a line break in it would add todo lines
+        let subject = item.subject.replace(['\r', '\n'], " ");
+        todo.push_str(&format!("{command} {hash} {}\n", subject.trim()));
+
+        if let Some(message) = new_message.filter(|value| !value.trim().is_empty()) {
+            let message_path = work_dir.join(format!("message-{index}"));
+            std::fs::write(&message_path, message)
+                .context("Failed to write rebase commit message")?;
+            todo.push_str(&format!(
+                "exec git commit --amend --allow-empty --quiet -F {}\n",
+                shell_quote(&message_path.to_string_lossy())
+            ));
         }
     }
 
-    Ok(MergePreview {
-        can_fast_forward,
-        conflicts,
-        files_changed,
-        insertions,
-        deletions,
-    })
-}
+    let todo_path = work_dir.join("git-rebase-todo");
+    std::fs::write(&todo_path, todo).context("Failed to write rebase todo list")?;
 
-pub fn merge_branch(repo_path: &Path, source: &str, message: Option<&str>) -> Result<MergeResult> {
-    let ff_status = Command::new("git")
+    let mut args = vec!["rebase", "-i", "--no-autosquash"];
+    if options.autostash {
+        args.push("--autostash");
+    }
+    match options.base.as_deref().map(|value| value.trim()) {
+        Some(base) if !base.is_empty() => args.push(base),
+        _ => args.push("--root"),
+    }
+
+    let output = git_command()
         .current_dir(repo_path)
-        .args(&["merge-base", "--is-ancestor", "HEAD", source])
+        .env(
+            "GIT_SEQUENCE_EDITOR",
+            format!("cp {}", shell_quote(&todo_path.to_string_lossy())),
+        )
+        .env("GIT_EDITOR", "true")
+        .args(&args)
         .output()
-        .context("Failed to check fast-forward")?;
-    let can_fast_forward = ff_status.status.success();
+        .context("Failed to execute git rebase -i")?;
 
-    let mut args = vec!["merge", "--no-commit"];
-    if let Some(msg) = message {
-        args.push("-m");
-        args.push(msg);
+    rebase_result(repo_path, &output, "rebase")
+}
+
+pub fn continue_rebase(repo_path: &Path) -> Result<RebaseResult> {
+    run_rebase_step(repo_path, "--continue")
+}
+
+pub fn skip_rebase(repo_path: &Path) -> Result<RebaseResult> {
+    run_rebase_step(repo_path, "--skip")
+}
+
+pub fn abort_rebase(repo_path: &Path) -> Result<RebaseResult> {
+    run_rebase_step(repo_path, "--abort")
+}
+
+fn run_rebase_step(repo_path: &Path, flag: &str) -> Result<RebaseResult> {
+    if !is_rebase_in_progress(repo_path) {
+        anyhow::bail!("No rebase in progress");
     }
-    args.push(source);
 
     let output = git_command()
         .current_dir(repo_path)
-        .args(&args)
+        .env("GIT_EDITOR", "true")
+        .args(["rebase", flag])
         .output()
-        .context("Failed to execute git merge")?;
+        .with_context(|| format!("Failed to execute git rebase {flag}"))?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-    let summary = if !stdout.is_empty() {
-        stdout
-    } else {
-        stderr.clone()
-    };
+    rebase_result(repo_path, &output, &format!("rebase {flag}"))
+}
 
-    // Com --no-commit, verificamos se há merge em progresso
-    let merge_started = is_merge_in_progress(repo_path);
+/// Interprets the output of a rebase command: stopping on a conflict or an `edit`
+/// is not an error, the caller gets the current state to drive the next step.
+fn rebase_result(
+    repo_path: &Path,
+    output: &std::process::Output,
+    action: &str,
+) -> Result<RebaseResult> {
+    let stdout = clean_terminal_output(&String::from_utf8_lossy(&output.stdout));
+    let stderr = clean_terminal_output(&String::from_utf8_lossy(&output.stderr));
+    let summary = [stdout.as_str(), stderr.as_str()]
+        .iter()
+        .filter(|value| !value.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n");
 
-    if !output.status.success() {
-        // Merge falhou - provavelmente há conflitos
-        if merge_started {
-            let conflicts = get_conflict_files(repo_path)?;
-            return Ok(MergeResult {
-                fast_forward: can_fast_forward,
-                summary,
-                conflicts,
-            });
-        }
+    let status = get_rebase_status(repo_path)?;
 
-        return Err(anyhow!("Git merge failed: {}", stderr));
+    if !status.in_progress {
+        if let Ok(work_dir) = git_dir_path(repo_path, REBASE_WORK_DIR) {
+            let _ = std::fs::remove_dir_all(work_dir);
+        }
+        if !output.status.success() {
+            anyhow::bail!("Git {} failed: {}", action, stderr);
+        }
     }
 
-    // Merge bem-sucedido com --no-commit
-    // Verifica se há conflitos pendentes mesmo assim
-    let conflicts = if merge_started {
-        get_conflict_files(repo_path)?
+    Ok(RebaseResult {
+        completed: !status.in_progress,
+        summary,
+        status,
+    })
+}
+
+pub fn get_rebase_status(repo_path: &Path) -> Result<RebaseStatus> {
+    let merge_dir = git_dir_path(repo_path, "rebase-merge")?;
+    let apply_dir = git_dir_path(repo_path, "rebase-apply")?;
+
+    let read = |path: PathBuf| -> Option<String> {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let (dir, step_file, total_file, interactive) = if merge_dir.exists() {
+        let interactive = merge_dir.join("interactive").exists();
+        (merge_dir, "msgnum", "end", interactive)
+    } else if apply_dir.exists() {
+        (apply_dir, "next", "last", false)
     } else {
-        Vec::new()
+        return Ok(RebaseStatus::default());
     };
 
-    Ok(MergeResult {
-        fast_forward: can_fast_forward,
-        summary,
-        conflicts,
+    let head_name = read(dir.join("head-name")).map(|name| {
+        name.strip_prefix("refs/heads/")
+            .map(|value| value.to_string())
+            .unwrap_or(name)
+    });
+    let head_name = head_name.filter(|name| name != "detached HEAD");
+
+    Ok(RebaseStatus {
+        in_progress: true,
+        interactive,
+        head_name,
+        onto: read(dir.join("onto")),
+        current_step: read(dir.join(step_file)).and_then(|value| value.parse().ok()),
+        total_steps: read(dir.join(total_file)).and_then(|value| value.parse().ok()),
+        stopped_at: read(dir.join("stopped-sha")),
+        conflicts: list_unmerged_files(repo_path)?,
     })
 }
 
-pub fn compare_branches(repo_path: &Path, base: &str, compare: &str) -> Result<BranchComparison> {
-    let rev_list = Command::new("git")
-        .current_dir(repo_path)
-        .args(&[
-            "rev-list",
-            "--left-right",
-            "--count",
-            &format!("{base}...{compare}"),
-        ])
-        .output()
-        .context("Failed to compare branches")?;
+// ============================================================================
+// Operations in Progress (merge, rebase, cherry-pick, revert, bisect, am)
+// ============================================================================
 
-    if !rev_list.status.success() {
-        anyhow::bail!(
-            "Git rev-list failed: {}",
-            String::from_utf8_lossy(&rev_list.stderr)
-        );
+/// Detects the operation currently stopped in the repository, if any.
+pub fn get_operation_in_progress(repo_path: &Path) -> Result<Option<OperationInProgress>> {
+    // All of these state files are per-worktree, so they live directly in the git dir
+    let git_dir = git_dir_path(repo_path, ".")?;
+    let exists = |name: &str| -> Result<bool> { Ok(git_dir.join(name).exists()) };
+    let read = |name: &str| -> Option<String> {
+        std::fs::read_to_string(git_dir.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    // `git am` also uses rebase-apply, marked with an "applying" file
+    if exists("rebase-apply/applying")? {
+        return Ok(Some(OperationInProgress {
+            kind: OperationKind::Am,
+            current_step: read("rebase-apply/next").and_then(|value| value.parse().ok()),
+            total_steps: read("rebase-apply/last").and_then(|value| value.parse().ok()),
+            head_name: None,
+            current_commit: None,
+            conflicts: list_unmerged_files(repo_path)?,
+            can_continue: true,
+            can_skip: true,
+        }));
     }
 
-    let mut ahead = 0usize;
-    let mut behind = 0usize;
-    let counts = String::from_utf8_lossy(&rev_list.stdout);
-    let parts: Vec<&str> = counts.split_whitespace().collect();
-    if parts.len() >= 2 {
-        behind = parts[0].parse::<usize>().unwrap_or(0);
-        ahead = parts[1].parse::<usize>().unwrap_or(0);
+    if exists("rebase-merge")? || exists("rebase-apply")? {
+        let status = get_rebase_status(repo_path)?;
+        return Ok(Some(OperationInProgress {
+            kind: OperationKind::Rebase,
+            current_step: status.current_step,
+            total_steps: status.total_steps,
+            head_name: status.head_name,
+            current_commit: status.stopped_at,
+            conflicts: status.conflicts,
+            can_continue: true,
+            can_skip: true,
+        }));
     }
 
-    let log_output = Command::new("git")
-        .current_dir(repo_path)
-        .args(&[
-            "log",
-            "--pretty=format:%H%x1f%B%x1f%an%x1f%ai%x1e",
-            &format!("{base}..{compare}"),
-        ])
-        .output()
-        .context("Failed to collect comparison log")?;
+    if exists("MERGE_HEAD")? {
+        return Ok(Some(OperationInProgress {
+            kind: OperationKind::Merge,
+            current_step: None,
+            total_steps: None,
+            head_name: current_branch_name(repo_path),
+            current_commit: read("MERGE_HEAD")
+                .and_then(|value| value.lines().next().map(|line| line.to_string())),
+            conflicts: list_unmerged_files(repo_path)?,
+            can_continue: true,
+            can_skip: false,
+        }));
+    }
 
-    let mut commits = Vec::new();
-    if log_output.status.success() {
-        let log_str = String::from_utf8_lossy(&log_output.stdout);
-        for record in log_str.split('\u{001e}') {
-            if record.trim().is_empty() {
-                continue;
-            }
+    for (head_file, kind) in [
+        ("CHERRY_PICK_HEAD", OperationKind::CherryPick),
+        ("REVERT_HEAD", OperationKind::Revert),
+    ] {
+        if !exists(head_file)? {
+            continue;
+        }
 
-            let parts: Vec<&str> = record.split('\u{001f}').collect();
-            if parts.len() < 4 {
-                continue;
-            }
+        let (current_step, total_steps) = sequencer_progress(repo_path);
+        return Ok(Some(OperationInProgress {
+            kind,
+            current_step,
+            total_steps,
+            head_name: current_branch_name(repo_path),
+            current_commit: read(head_file),
+            conflicts: list_unmerged_files(repo_path)?,
+            can_continue: true,
+            can_skip: true,
+        }));
+    }
 
-            commits.push(CommitInfo {
-                hash: parts[0].trim().to_string(),
-                message: parts[1].to_string(),
-                author: parts[2].trim().to_string(),
-                date: parts[3].trim().to_string(),
-            });
-        }
+    if exists("BISECT_LOG")? {
+        return Ok(Some(OperationInProgress {
+            kind: OperationKind::Bisect,
+            current_step: None,
+            total_steps: None,
+            head_name: read("BISECT_START"),
+            current_commit: None,
+            conflicts: Vec::new(),
+            can_continue: false,
+            can_skip: true,
+        }));
     }
 
-    let diff_output = Command::new("git")
+    Ok(None)
+}
+
+/// Progress of a multi-commit cherry-pick/revert: commits already applied since
+/// `sequencer/head` plus the ones left in `sequencer/todo` (the first is the current one).
+fn sequencer_progress(repo_path: &Path) -> (Option<usize>, Option<usize>) {
+    let Ok(todo_path) = git_dir_path(repo_path, "sequencer/todo") else {
+        return (None, None);
+    };
+    let Ok(todo) = std::fs::read_to_string(todo_path) else {
+        return (Some(1), Some(1));
+    };
+
+    let remaining = todo
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .count();
+
+    let done = git_dir_path(repo_path, "sequencer/head")
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|head| {
+            Command::new("git")
+                .current_dir(repo_path)
+                .args(["rev-list", "--count", &format!("{}..HEAD", head.trim())])
+                .output()
+                .ok()
+        })
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+        .unwrap_or(0usize);
+
+    (Some(done + 1), Some(done + remaining.max(1)))
+}
+
+fn current_branch_name(repo_path: &Path) -> Option<String> {
+    let output = Command::new("git")
         .current_dir(repo_path)
-        .args(&["diff", "--stat", &format!("{base}..{compare}")])
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
         .output()
-        .context("Failed to compute diff summary")?;
+        .ok()?;
 
-    let diff_summary = if diff_output.status.success() {
-        String::from_utf8_lossy(&diff_output.stdout)
-            .trim()
-            .to_string()
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !name.is_empty() {
+        Some(name)
     } else {
-        String::new()
+        None
+    }
+}
+
+pub fn continue_operation(repo_path: &Path) -> Result<OperationResult> {
+    run_operation_step(repo_path, "--continue")
+}
+
+pub fn abort_operation(repo_path: &Path) -> Result<OperationResult> {
+    run_operation_step(repo_path, "--abort")
+}
+
+pub fn skip_operation(repo_path: &Path) -> Result<OperationResult> {
+    run_operation_step(repo_path, "--skip")
+}
+
+fn run_operation_step(repo_path: &Path, step: &str) -> Result<OperationResult> {
+    let operation =
+        get_operation_in_progress(repo_path)?.ok_or_else(|| anyhow!("No operation in progress"))?;
+
+    let args: Vec<&str> = match (operation.kind, step) {
+        (OperationKind::Merge, "--continue") => {
+            if !operation.conflicts.is_empty() {
+                anyhow::bail!("Unresolved conflicts: {:?}", operation.conflicts);
+            }
+            vec!["commit", "--no-edit"]
+        }
+        (OperationKind::Merge, "--abort") => vec!["merge", "--abort"],
+        (OperationKind::Merge, _) => anyhow::bail!("A merge cannot be skipped"),
+        (OperationKind::Rebase, _) => vec!["rebase", step],
+        (OperationKind::CherryPick, _) => vec!["cherry-pick", step],
+        (OperationKind::Revert, _) => vec!["revert", step],
+        (OperationKind::Am, _) => vec!["am", step],
+        (OperationKind::Bisect, "--abort") => vec!["bisect", "reset"],
+        (OperationKind::Bisect, "--skip") => vec!["bisect", "skip"],
+        (OperationKind::Bisect, _) => anyhow::bail!("A bisect cannot be continued"),
     };
 
-    Ok(BranchComparison {
-        ahead,
-        behind,
-        commits,
-        diff_summary,
+    let output = git_command()
+        .current_dir(repo_path)
+        .env("GIT_EDITOR", "true")
+        .args(&args)
+        .output()
+        .with_context(|| format!("Failed to execute git {}", args.join(" ")))?;
+
+    let stdout = clean_terminal_output(&String::from_utf8_lossy(&output.stdout));
+    let stderr = clean_terminal_output(&String::from_utf8_lossy(&output.stderr));
+
+    // Stopping again (next conflict, edit) is a valid outcome; only fail when nothing moved
+    let next = get_operation_in_progress(repo_path)?;
+    if !output.status.success() && (next.is_none() || step == "--abort") {
+        anyhow::bail!("Git {} failed: {}", args.join(" "), stderr);
+    }
+
+    if next.is_none() {
+        if let Ok(work_dir) = git_dir_path(repo_path, REBASE_WORK_DIR) {
+            let _ = std::fs::remove_dir_all(work_dir);
+        }
+    }
+
+    let summary = [stdout, stderr]
+        .into_iter()
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(OperationResult {
+        completed: next.is_none(),
+        summary,
+        operation: next,
     })
 }
 
@@ -1912,16 +7433,418 @@ pub fn compare_branches(repo_path: &Path, base: &str, compare: &str) -> Result<B
 // ============================================================================
 
 /// Clone a remote repository to a local destination
-pub fn clone_repository(url: &str, destination: &str) -> Result<String> {
-    let output = Command::new("git")
-        .args(&["clone", url, destination])
-        .output()
-        .context("Failed to execute git clone")?;
+pub fn clone_repository(url: &str, destination: &str, options: &CloneOptions) -> Result<String> {
+    clone_repository_with_progress(url, destination, options, &OperationControl::none())
+}
+
+/// Clones with `options`, reporting progress. If the clone fails or is cancelled, whatever
+/// was created at `destination` is removed again.
+pub fn clone_repository_with_progress(
+    url: &str,
+    destination: &str,
+    options: &CloneOptions,
+    control: &OperationControl,
+) -> Result<String> {
+    let destination_path = Path::new(destination);
+    let existed = destination_path.exists();
+    // git clone refuses non-empty directories; never clean up something we did not create
+    let was_empty = !existed
+        || std::fs::read_dir(destination_path)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+
+    let result = run_clone(url, destination_path, options, control);
+    if result.is_err() && was_empty {
+        remove_partial_clone(destination_path, existed);
+    }
+    result.map(|_| destination.to_string())
+}
+
+fn run_clone(
+    url: &str,
+    destination: &Path,
+    options: &CloneOptions,
+    control: &OperationControl,
+) -> Result<()> {
+    let mut args: Vec<String> = vec!["clone".into(), "--progress".into()];
+    if let Some(branch) = options.branch.as_deref().filter(|b| !b.trim().is_empty()) {
+        args.push("--branch".into());
+        args.push(branch.trim().to_string());
+    }
+    if let Some(depth) = options.depth {
+        if depth == 0 {
+            anyhow::bail!("Clone depth must be at least 1");
+        }
+        args.push(format!("--depth={}", depth));
+    }
+    // --depth implies --single-branch, so only pass it when asked for explicitly
+    if options.single_branch {
+        args.push("--single-branch".into());
+    }
+    if let Some(filter) = options.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+        args.push(format!("--filter={}", filter.trim()));
+    }
+    let sparse = !options.sparse_paths.is_empty();
+    if sparse {
+        args.push("--sparse".into());
+    }
+    if options.recurse_submodules {
+        args.push("--recurse-submodules".into());
+        if options.depth.is_some() {
+            args.push("--shallow-submodules".into());
+        }
+    }
+    args.push("--".into());
+    args.push(url.to_string());
+    args.push(destination.to_string_lossy().to_string());
+
+    let mut command = Command::new("git");
+    command.args(&args);
+    let output = run_with_progress(command, control).context("Failed to execute git clone")?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!("Git clone failed: {}", stderr);
     }
 
-    Ok(destination.to_string())
+    if sparse {
+        // Cone mode only understands directories; anything glob-like needs full patterns
+        let cone = options
+            .sparse_paths
+            .iter()
+            .all(|path| !path.contains(['*', '?', '[', '!']));
+        let mut command = Command::new("git");
+        command
+            .current_dir(destination)
+            .args(["sparse-checkout", "set"])
+            .arg(if cone { "--cone" } else { "--no-cone" })
+            .arg("--")
+            .args(&options.sparse_paths);
+        let output =
+            run_with_progress(command, control).context("Failed to execute git sparse-checkout")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Git sparse-checkout failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        // Submodules outside the initial sparse checkout were skipped by the clone
+        if options.recurse_submodules {
+            let mut command = Command::new("git");
+            command.current_dir(destination).args([
+                "submodule",
+                "update",
+                "--init",
+                "--recursive",
+                "--progress",
+            ]);
+            let output = run_with_progress(command, control)
+                .context("Failed to execute git submodule update")?;
+
+            if !output.status.success() {
+                anyhow::bail!(
+                    "Git submodule update failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes a partially cloned destination, keeping the directory itself if it already
+/// existed (empty) before the clone.
+fn remove_partial_clone(destination: &Path, keep_directory: bool) {
+    if !keep_directory {
+        let _ = std::fs::remove_dir_all(destination);
+        return;
+    }
+
+    let Ok(entries) = std::fs::read_dir(destination) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let _ = if path.is_dir() && !path.is_symlink() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ========================================================================
+    // Progress
+    // ========================================================================
+
+    #[test]
+    fn parses_progress_with_counts_and_throughput() {
+        let progress =
+            parse_progress_line("Receiving objects:  45% (9/20), 1.20 MiB | 800.00 KiB/s").unwrap();
+        assert_eq!(progress.phase, "Receiving objects");
+        assert!(!progress.remote);
+        assert_eq!(progress.percent, Some(45));
+        assert_eq!(progress.current, Some(9));
+        assert_eq!(progress.total, Some(20));
+        assert_eq!(
+            progress.throughput.as_deref(),
+            Some("1.20 MiB | 800.00 KiB/s")
+        );
+        assert!(!progress.done);
+    }
+
+    #[test]
+    fn parses_finished_progress() {
+        let progress = parse_progress_line("Resolving deltas: 100% (12/12), done.").unwrap();
+        assert_eq!(progress.percent, Some(100));
+        assert_eq!(progress.current, Some(12));
+        assert_eq!(progress.total, Some(12));
+        assert_eq!(progress.throughput, None);
+        assert!(progress.done);
+    }
+
+    #[test]
+    fn parses_remote_progress() {
+        let progress = parse_progress_line("remote: Enumerating objects: 5, done.").unwrap();
+        assert_eq!(progress.phase, "Enumerating objects");
+        assert!(progress.remote);
+        assert_eq!(progress.percent, None);
+        assert_eq!(progress.current, Some(5));
+        assert!(progress.done);
+    }
+
+    #[test]
+    fn ignores_lines_that_are_not_progress() {
+        assert_eq!(parse_progress_line("fatal: repository not found"), None);
+        assert_eq!(parse_progress_line("remote: "), None);
+        assert_eq!(parse_progress_line(""), None);
+    }
+
+    #[test]
+    fn splits_progress_redraws_into_lines() {
+        let stderr: &[u8] =
+            b"Receiving objects:  10% (1/10)\rReceiving objects: 100% (10/10), done.\n\
+remote: Total 10 (delta 0)\r\n";
+        let mut lines = Vec::new();
+        for_each_progress_line(stderr, |line| {
+            lines.push(String::from_utf8_lossy(line).into_owned())
+        });
+        assert_eq!(
+            lines,
+            [
+                "Receiving objects:  10% (1/10)",
+                "Receiving objects: 100% (10/10), done.",
+                "remote: Total 10 (delta 0)",
+                "",
+                "",
+            ]
+        );
+
+        let parsed: Vec<GitProgress> = lines
+            .iter()
+            .filter_map(|line| parse_progress_line(line))
+            .collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].percent, Some(10));
+        assert!(parsed[1].done);
+    }
+
+    // ========================================================================
+    // Status
+    // ========================================================================
+
+    fn entries<'a>(status: &'a RepoStatus, path: &str) -> Vec<&'a FileStatus> {
+        status
+            .files
+            .iter()
+            .filter(|file| file.path == path)
+            .collect()
+    }
+
+    #[test]
+    fn parses_status_v2_entries() {
+        let output = [
+            "# branch.oid 1f2e3d4c5b6a",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +1 -2",
+            "1 .M N... 100644 100644 100644 aaaa aaaa src/lib.rs",
+            "2 RM N... 100644 100644 100644 bbbb bbbb R100 new name.rs",
+            "old name.rs",
+            "2 C. N... 100644 100644 100644 cccc cccc C75 copy.rs",
+            "orig.rs",
+            "u UU N... 100644 100644 100644 100644 dddd eeee ffff conflict.rs",
+            "? untracked file.txt",
+            "",
+        ]
+        .join("\0");
+        let status = parse_status_v2(&output);
+
+        assert_eq!(status.current_branch, "main");
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!(status.head_commit.as_deref(), Some("1f2e3d4c5b6a"));
+        assert_eq!((status.ahead, status.behind), (1, 2));
+        assert!(!status.detached);
+
+        let modified = entries(&status, "src/lib.rs");
+        assert_eq!(modified.len(), 1);
+        assert!(!modified[0].staged);
+        assert_eq!(modified[0].orig_path, None);
+
+        let renamed = entries(&status, "new name.rs");
+        assert_eq!(renamed.len(), 2);
+        let staged = renamed.iter().find(|file| file.staged).unwrap();
+        assert_eq!(staged.index_status.as_deref(), Some("Renamed"));
+        assert_eq!(staged.orig_path.as_deref(), Some("old name.rs"));
+        let unstaged = renamed.iter().find(|file| !file.staged).unwrap();
+        assert_eq!(unstaged.worktree_status.as_deref(), Some("Modified"));
+        assert!(entries(&status, "old name.rs").is_empty());
+
+        let copied = entries(&status, "copy.rs");
+        assert_eq!(copied.len(), 1);
+        assert!(copied[0].staged);
+        assert_eq!(copied[0].orig_path.as_deref(), Some("orig.rs"));
+        assert!(entries(&status, "orig.rs").is_empty());
+
+        let conflicted = entries(&status, "conflict.rs");
+        assert_eq!(conflicted.len(), 1);
+        assert_eq!(conflicted[0].conflict, Some(ConflictKind::BothModified));
+
+        let untracked = entries(&status, "untracked file.txt");
+        assert_eq!(untracked.len(), 1);
+        assert!(!untracked[0].staged);
+    }
+
+    #[test]
+    fn parses_detached_status_before_the_first_commit() {
+        let status = parse_status_v2("# branch.oid (initial)\0# branch.head (detached)\0");
+        assert!(status.detached);
+        assert_eq!(status.head_commit, None);
+        assert_eq!(status.upstream, None);
+        assert!(status.files.is_empty());
+    }
+
+    // ========================================================================
+    // Graph
+    // ========================================================================
+
+    fn commit(hash: &str, parents: &[&str]) -> CommitInfo {
+        CommitInfo {
+            hash: hash.to_string(),
+            message: String::new(),
+            author: String::new(),
+            date: String::new(),
+            author_email: String::new(),
+            committer: String::new(),
+            committer_email: String::new(),
+            committer_date: String::new(),
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+            refs: Vec::new(),
+        }
+    }
+
+    fn layout(graph: &CommitGraph) -> Vec<(usize, Vec<(usize, usize)>)> {
+        graph
+            .rows
+            .iter()
+            .map(|row| {
+                let mut edges: Vec<(usize, usize)> = row
+                    .edges
+                    .iter()
+                    .map(|edge| (edge.from_column, edge.to_column))
+                    .collect();
+                edges.sort_unstable();
+                (row.column, edges)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lays_out_a_merge() {
+        let graph = layout_commit_graph(vec![
+            commit("m", &["a", "b"]),
+            commit("a", &["c"]),
+            commit("b", &["c"]),
+            commit("c", &[]),
+        ]);
+        assert_eq!(
+            layout(&graph),
+            [
+                (0, vec![(0, 0), (0, 1)]),
+                (0, vec![(0, 0), (1, 1)]),
+                (1, vec![(0, 0), (1, 0)]),
+                (0, vec![]),
+            ]
+        );
+        assert_eq!(graph.max_columns, 2);
+        assert_eq!(graph.rows[0].color, graph.rows[1].color);
+        assert_ne!(graph.rows[0].color, graph.rows[2].color);
+    }
+
+    #[test]
+    fn lays_out_an_octopus_merge() {
+        let graph = layout_commit_graph(vec![
+            commit("o", &["p1", "p2", "p3"]),
+            commit("p1", &["r"]),
+            commit("p2", &["r"]),
+            commit("p3", &["r"]),
+            commit("r", &[]),
+        ]);
+        assert_eq!(
+            layout(&graph),
+            [
+                (0, vec![(0, 0), (0, 1), (0, 2)]),
+                (0, vec![(0, 0), (1, 1), (2, 2)]),
+                (1, vec![(0, 0), (1, 0), (2, 2)]),
+                (2, vec![(0, 0), (2, 0)]),
+                (0, vec![]),
+            ]
+        );
+        assert_eq!(graph.max_columns, 3);
+    }
+
+    // ========================================================================
+    // Tags
+    // ========================================================================
+
+    #[test]
+    fn sorts_tags_by_semantic_version() {
+        let mut names = vec![
+            "release",
+            "1.0.0-alpha",
+            "v1.9.0",
+            "1.0.0-alpha.1",
+            "v1.10.0",
+            "nightly",
+            "1.0.0-rc.1",
+            "v1.0.0",
+            "1.0.0-beta",
+            "v2.0.0+build.5",
+            "1.0.0-alpha.beta",
+        ];
+        names.sort_by_cached_key(|name| tag_order_key(name));
+        assert_eq!(
+            names,
+            [
+                "v2.0.0+build.5",
+                "v1.10.0",
+                "v1.9.0",
+                "v1.0.0",
+                "1.0.0-rc.1",
+                "1.0.0-beta",
+                "1.0.0-alpha.beta",
+                "1.0.0-alpha.1",
+                "1.0.0-alpha",
+                "nightly",
+                "release",
+            ]
+        );
+    }
 }
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+use crate::git::{GitProgress, OperationControl};
+
+/// Progress of a running job, emitted to the owning window (or every window without a context).
+pub const JOB_PROGRESS_EVENT: &str = "git-job-progress";
+/// Final result of a job, emitted once it completes, fails or is cancelled.
+pub const JOB_FINISHED_EVENT: &str = "git-job-finished";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    /// "push", "pull", "fetch", "clone"...
+    pub kind: String,
+    pub context_key: Option<String>,
+    pub last_progress: Option<GitProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobProgressEvent {
+    pub job_id: String,
+    pub kind: String,
+    pub context_key: Option<String>,
+    pub progress: GitProgress,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobFinishedEvent {
+    pub job_id: String,
+    pub kind: String,
+    pub context_key: Option<String>,
+    pub success: bool,
+    pub cancelled: bool,
+    /// What the operation returned, e.g. the push output or a `PullResult`
+    pub output: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+struct Job {
+    info: JobInfo,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Long-running git operations executed on background threads.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    next_id: AtomicU64,
+}
+
+impl JobRegistry {
+    /// Runs `run` on a background thread and returns the job id right away.
+    /// Progress and the final result are delivered as events.
+    pub fn spawn<F, T>(
+        &self,
+        app: AppHandle,
+        kind: &str,
+        context_key: Option<String>,
+        run: F,
+    ) -> String
+    where
+        F: FnOnce(&OperationControl) -> anyhow::Result<T> + Send + 'static,
+        T: Serialize,
+    {
+        let id = format!("job-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let info = JobInfo {
+            id: id.clone(),
+            kind: kind.to_string(),
+            context_key,
+            last_progress: None,
+        };
+
+        self.jobs.lock().unwrap().insert(
+            id.clone(),
+            Job {
+                info: info.clone(),
+                cancelled: cancelled.clone(),
+            },
+        );
+
+        let jobs = self.jobs.clone();
+        std::thread::spawn(move || {
+            let progress_app = app.clone();
+            let progress_jobs = jobs.clone();
+            let progress_info = info.clone();
+            let control = OperationControl::new(
+                cancelled.clone(),
+                Box::new(move |progress: GitProgress| {
+                    if let Some(job) = progress_jobs.lock().unwrap().get_mut(&progress_info.id) {
+                        job.info.last_progress = Some(progress.clone());
+                    }
+                    emit(
+                        &progress_app,
+                        progress_info.context_key.as_deref(),
+                        JOB_PROGRESS_EVENT,
+                        JobProgressEvent {
+                            job_id: progress_info.id.clone(),
+                            kind: progress_info.kind.clone(),
+                            context_key: progress_info.context_key.clone(),
+                            progress,
+                        },
+                    );
+                }),
+            );
+
+            let result = run(&control);
+            jobs.lock().unwrap().remove(&info.id);
+
+            let was_cancelled = control.is_cancelled();
+            let result = result.and_then(|output| Ok(serde_json::to_value(output)?));
+            let (output, error) = match result {
+                Ok(output) => (Some(output), None),
+                Err(err) => (None, Some(crate::locks::error_message(err))),
+            };
+            let finished = JobFinishedEvent {
+                job_id: info.id.clone(),
+                kind: info.kind.clone(),
+                context_key: info.context_key.clone(),
+                success: error.is_none(),
+                cancelled: was_cancelled,
+                output,
+                error,
+            };
+            emit(
+                &app,
+                info.context_key.as_deref(),
+                JOB_FINISHED_EVENT,
+                finished,
+            );
+        });
+
+        id
+    }
+
+    /// Requests cancellation; the git process is killed and the job finishes as cancelled.
+    /// Returns false if the job is unknown or already finished.
+    pub fn cancel(&self, job_id: &str) -> bool {
+        match self.jobs.lock().unwrap().get(job_id) {
+            Some(job) => {
+                job.cancelled.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|job| job.info.clone())
+            .collect();
+        jobs.sort_by(|a, b| a.id.len().cmp(&b.id.len()).then_with(|| a.id.cmp(&b.id)));
+        jobs
+    }
+}
+
+fn emit<S: Serialize + Clone>(app: &AppHandle, context_key: Option<&str>, event: &str, payload: S) {
+    let _ = match context_key {
+        Some(context_key) => {
+            app.emit_to(crate::commands::window_label(context_key), event, payload)
+        }
+        None => app.emit(event, payload),
+    };
+}
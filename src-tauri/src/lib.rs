@@ -2,7 +2,10 @@ mod ai;
 mod commands;
 mod config;
 mod git;
+mod jobs;
+mod locks;
 mod setup;
+mod watcher;
 
 use commands::AppState;
 use std::collections::HashMap;
@@ -19,49 +22,103 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .manage(AppState {
             repos: Mutex::new(HashMap::new()),
+            watchers: watcher::WatcherRegistry::default(),
+            jobs: jobs::JobRegistry::default(),
+            locks: locks::RepoLocks::default(),
         })
         .invoke_handler(tauri::generate_handler![
             commands::set_repository,
             commands::unset_repository,
             commands::unset_tab_repository,
+            commands::watch_repository_cmd,
+            commands::unwatch_repository_cmd,
             commands::init_repository_cmd,
             commands::publish_github_repo_cmd,
             commands::get_current_repo_path,
             commands::get_git_status,
             commands::get_file_diff,
             commands::get_all_diff_cmd,
+            commands::get_all_diff_files_cmd,
             commands::stage_file_cmd,
             commands::stage_all_cmd,
             commands::unstage_file_cmd,
+            commands::get_file_diff_hunks_cmd,
+            commands::stage_hunks_cmd,
+            commands::unstage_hunks_cmd,
+            commands::discard_hunks_cmd,
+            commands::discard_files_cmd,
+            commands::clean_untracked_cmd,
+            commands::get_discard_snapshots_cmd,
+            commands::undo_discard_cmd,
+            commands::delete_discard_snapshot_cmd,
             commands::commit_cmd,
             commands::amend_commit_cmd,
             commands::is_last_commit_pushed_cmd,
             commands::push_cmd,
             commands::pull_cmd,
+            commands::get_pull_strategy_cmd,
+            commands::set_pull_strategy_cmd,
             commands::get_branches_cmd,
             commands::fetch_prune_cmd,
+            // Background Jobs
+            commands::start_push_job_cmd,
+            commands::start_pull_job_cmd,
+            commands::start_fetch_prune_job_cmd,
+            commands::start_clone_job_cmd,
+            commands::cancel_job_cmd,
+            commands::get_jobs_cmd,
+            // Repository Locks
+            commands::get_repo_operations_cmd,
+            commands::get_index_lock_cmd,
+            commands::remove_stale_index_lock_cmd,
             commands::checkout_branch_cmd,
             commands::checkout_remote_branch_cmd,
             commands::create_branch_cmd,
             commands::delete_branch_cmd,
+            commands::get_stale_branches_cmd,
+            commands::delete_stale_branches_cmd,
+            commands::rename_branch_cmd,
+            commands::set_branch_upstream_cmd,
+            commands::unset_branch_upstream_cmd,
             // Context Menu Commands
             commands::reset_cmd,
             commands::cherry_pick_cmd,
             commands::revert_cmd,
             commands::checkout_commit_cmd,
             commands::create_tag_cmd,
+            commands::get_tags_cmd,
+            commands::show_tag_cmd,
+            commands::delete_tag_cmd,
+            commands::push_tags_cmd,
             commands::get_commit_diff_cmd,
+            commands::get_commit_diff_files_cmd,
             // Merge Commands
             commands::merge_preview_cmd,
             commands::merge_branch_cmd,
             commands::is_merge_in_progress_cmd,
             commands::get_conflict_files_cmd,
             commands::parse_conflict_file_cmd,
+            commands::checkout_conflict_style_cmd,
             commands::resolve_conflict_file_cmd,
+            commands::resolve_conflict_with_side_cmd,
+            commands::resolve_conflict_as_deleted_cmd,
+            commands::get_lockfile_conflicts_cmd,
+            commands::resolve_lockfile_conflict_cmd,
             commands::complete_merge_cmd,
             commands::compare_branches_cmd,
             commands::get_commit_log,
+            commands::get_commit_graph_cmd,
+            commands::get_file_history_cmd,
+            commands::get_blame_cmd,
+            commands::get_file_at_revision_cmd,
+            commands::restore_paths_cmd,
             commands::get_remote_origin_url_cmd,
+            // Remotes
+            commands::get_remotes_cmd,
+            commands::add_remote_cmd,
+            commands::rename_remote_cmd,
+            commands::remove_remote_cmd,
+            commands::set_remote_url_cmd,
             commands::get_commit_shortstat_cmd,
             commands::generate_commit_msg,
             commands::ai_chat,
@@ -77,6 +134,25 @@ pub fn run() {
             commands::install_git_cmd,
             commands::install_gh_cmd,
             commands::authenticate_gh_cmd,
+            // Stash Commands
+            commands::get_stashes_cmd,
+            commands::create_stash_cmd,
+            commands::apply_stash_cmd,
+            commands::pop_stash_cmd,
+            commands::drop_stash_cmd,
+            commands::get_stash_diff_cmd,
+            // Rebase Commands
+            commands::get_rebase_todo_cmd,
+            commands::start_interactive_rebase_cmd,
+            commands::continue_rebase_cmd,
+            commands::skip_rebase_cmd,
+            commands::abort_rebase_cmd,
+            commands::get_rebase_status_cmd,
+            // Operation in Progress Commands
+            commands::get_operation_in_progress_cmd,
+            commands::continue_operation_cmd,
+            commands::abort_operation_cmd,
+            commands::skip_operation_cmd,
             // Worktree Commands
             commands::get_worktrees_cmd,
             commands::remove_worktree_cmd,
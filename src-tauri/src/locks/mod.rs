@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::git::{self, OperationControl};
+
+/// How often a waiting caller re-checks for cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a caller is willing to wait for the operations queued before it.
+#[derive(Clone, Copy)]
+pub enum Wait<'a> {
+    /// Give up with a "busy" error once the timeout expires
+    Timeout(Duration),
+    /// Wait as long as needed, unless the operation is cancelled
+    UntilCancelled(&'a OperationControl),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationState {
+    Running,
+    Queued,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoOperation {
+    pub id: u64,
+    /// "commit", "push", "checkout"...
+    pub kind: String,
+    pub context_key: Option<String>,
+    pub state: OperationState,
+    pub queued_at: String,
+    pub started_at: Option<String>,
+}
+
+#[derive(Default)]
+struct RepoQueue {
+    running: Option<RepoOperation>,
+    waiting: VecDeque<RepoOperation>,
+}
+
+impl RepoQueue {
+    fn is_empty(&self) -> bool {
+        self.running.is_none() && self.waiting.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    queues: Mutex<HashMap<PathBuf, RepoQueue>>,
+    released: Condvar,
+    next_id: AtomicU64,
+}
+
+/// Serializes write operations per repository, so tabs, windows and background jobs working
+/// on the same repository (or on worktrees of it) never run git concurrently.
+#[derive(Clone, Default)]
+pub struct RepoLocks {
+    inner: Arc<Inner>,
+}
+
+/// Held while an operation runs; the next queued operation starts when it is dropped.
+pub struct OperationGuard {
+    inner: Arc<Inner>,
+    key: PathBuf,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let mut queues = self.inner.queues.lock().unwrap();
+        if let Some(queue) = queues.get_mut(&self.key) {
+            queue.running = None;
+            if queue.is_empty() {
+                queues.remove(&self.key);
+            }
+        }
+        self.inner.released.notify_all();
+    }
+}
+
+impl RepoLocks {
+    /// Queues an operation on `repo_path` and blocks until every operation queued before it
+    /// has finished. Operations run in the order they were queued.
+    pub fn acquire(
+        &self,
+        repo_path: &Path,
+        kind: &str,
+        context_key: Option<&str>,
+        wait: Wait,
+    ) -> Result<OperationGuard> {
+        let key = lock_key(repo_path);
+        let id = self.inner.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let deadline = match wait {
+            Wait::Timeout(timeout) => Some(Instant::now() + timeout),
+            Wait::UntilCancelled(_) => None,
+        };
+
+        let mut queues = self.inner.queues.lock().unwrap();
+        queues
+            .entry(key.clone())
+            .or_default()
+            .waiting
+            .push_back(RepoOperation {
+                id,
+                kind: kind.to_string(),
+                context_key: context_key.map(str::to_string),
+                state: OperationState::Queued,
+                queued_at: Utc::now().to_rfc3339(),
+                started_at: None,
+            });
+
+        loop {
+            let queue = queues.entry(key.clone()).or_default();
+
+            if queue.running.is_none() && queue.waiting.front().map(|op| op.id) == Some(id) {
+                let mut operation = queue.waiting.pop_front().unwrap();
+                operation.state = OperationState::Running;
+                operation.started_at = Some(Utc::now().to_rfc3339());
+                queue.running = Some(operation);
+                return Ok(OperationGuard {
+                    inner: self.inner.clone(),
+                    key,
+                });
+            }
+
+            let error = match wait {
+                Wait::Timeout(_) if deadline.is_some_and(|d| Instant::now() >= d) => {
+                    let running = queue
+                        .running
+                        .as_ref()
+                        .map(|op| op.kind.as_str())
+                        .unwrap_or("another operation");
+                    Some(anyhow!(
+                        "Repository is busy: {} is still running. Try again when it finishes.",
+                        running
+                    ))
+                }
+                Wait::UntilCancelled(control) if control.is_cancelled() => {
+                    Some(anyhow!("Operation cancelled"))
+                }
+                _ => None,
+            };
+
+            if let Some(error) = error {
+                queue.waiting.retain(|op| op.id != id);
+                if queue.is_empty() {
+                    queues.remove(&key);
+                }
+                // We may have been at the front; let the next operation in line proceed
+                self.inner.released.notify_all();
+                return Err(error);
+            }
+
+            let timeout = deadline
+                .map(|d| {
+                    d.saturating_duration_since(Instant::now())
+                        .min(POLL_INTERVAL)
+                })
+                .unwrap_or(POLL_INTERVAL);
+            queues = self.inner.released.wait_timeout(queues, timeout).unwrap().0;
+        }
+    }
+
+    /// The running operation (if any) followed by queued ones, in order.
+    pub fn operations(&self, repo_path: &Path) -> Vec<RepoOperation> {
+        let key = lock_key(repo_path);
+        let queues = self.inner.queues.lock().unwrap();
+        queues
+            .get(&key)
+            .map(|queue| {
+                queue
+                    .running
+                    .iter()
+                    .chain(queue.waiting.iter())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Turns a git error into a message for the UI, explaining `index.lock` contention.
+pub fn error_message(err: anyhow::Error) -> String {
+    let message = err.to_string();
+    if git::is_index_lock_error(&message) {
+        return format!(
+            "Another git process is using this repository (index.lock exists). \
+             Wait for it to finish, or remove the lock if it was left behind by a crash.\n\n{}",
+            message
+        );
+    }
+    message
+}
+
+/// Worktrees share refs and objects with the main repository, so they share one queue.
+fn lock_key(repo_path: &Path) -> PathBuf {
+    git::get_common_git_dir(repo_path)
+        .ok()
+        .and_then(|dir| dir.canonicalize().ok())
+        .unwrap_or_else(|| repo_path.to_path_buf())
+}
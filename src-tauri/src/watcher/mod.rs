@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use crate::git::{self, RepoStatus};
+
+/// Event emitted to the owning window when a watched repository's status changes.
+pub const STATUS_CHANGED_EVENT: &str = "repo-status-changed";
+
+/// Quiet period before a burst of file-system events is processed.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Upper bound on how long a continuous stream of events can delay a refresh.
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoStatusChanged {
+    pub context_key: String,
+    pub status: RepoStatus,
+}
+
+/// Cheap fingerprint of the index, HEAD and the upstream commit. Local operations run from
+/// the app change the index, the checked-out branch or the commit it points to, and push,
+/// fetch and pull move the upstream, so a cached status is never served right after them,
+/// even if the watcher has not delivered the corresponding events yet.
+#[derive(Debug, Clone, PartialEq)]
+struct RepoStamp {
+    index_modified: Option<SystemTime>,
+    index_len: Option<u64>,
+    /// The HEAD file: which branch is checked out
+    head: Option<String>,
+    /// A soft reset or `git branch -f` moves it without touching the index
+    head_commit: Option<String>,
+    /// Ahead/behind counts are relative to it
+    upstream: Option<String>,
+}
+
+type StatusCache = Arc<Mutex<Option<(RepoStamp, RepoStatus)>>>;
+
+struct RepoWatch {
+    repo_path: PathBuf,
+    git_dir: PathBuf,
+    cache: StatusCache,
+    /// Cleared when the watch is replaced or removed
+    active: Arc<Mutex<bool>>,
+    // Dropping the watcher closes the event channel, which stops the worker thread
+    _watcher: RecommendedWatcher,
+}
+
+impl Drop for RepoWatch {
+    fn drop(&mut self) {
+        // A refresh already running must not report this repository any more
+        *self.active.lock().unwrap() = false;
+    }
+}
+
+/// File-system watchers per `context_key`, with the last known status of each repository.
+#[derive(Default)]
+pub struct WatcherRegistry {
+    watchers: Mutex<HashMap<String, RepoWatch>>,
+}
+
+impl WatcherRegistry {
+    /// Starts watching the working tree and git directory of `repo_path` for `context_key`,
+    /// replacing any previous watcher for that context.
+    pub fn watch(&self, app: AppHandle, context_key: &str, repo_path: &Path) -> Result<()> {
+        let repo_path = repo_path
+            .canonicalize()
+            .context("Failed to resolve repository path")?;
+        let git_dir = git::get_git_dir(&repo_path)?
+            .canonicalize()
+            .context("Failed to resolve git directory")?;
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .context("Failed to create file watcher")?;
+
+        watcher
+            .watch(&repo_path, RecursiveMode::Recursive)
+            .context("Failed to watch repository")?;
+        // Linked worktrees keep their git directory outside the working tree
+        if !git_dir.starts_with(&repo_path) {
+            watcher
+                .watch(&git_dir, RecursiveMode::Recursive)
+                .context("Failed to watch git directory")?;
+        }
+
+        let cache: StatusCache = Arc::new(Mutex::new(None));
+        let active = Arc::new(Mutex::new(true));
+        let worker = Worker {
+            app,
+            context_key: context_key.to_string(),
+            repo_path: repo_path.clone(),
+            git_dir: git_dir.clone(),
+            cache: cache.clone(),
+            active: active.clone(),
+        };
+        std::thread::spawn(move || worker.run(rx));
+
+        self.watchers.lock().unwrap().insert(
+            context_key.to_string(),
+            RepoWatch {
+                repo_path,
+                git_dir,
+                cache,
+                active,
+                _watcher: watcher,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn unwatch(&self, context_key: &str) {
+        self.watchers.lock().unwrap().remove(context_key);
+    }
+
+    /// Status of a watched repository, served from the cache while nothing has changed.
+    pub fn get_status(&self, context_key: &str, repo_path: &Path) -> Result<RepoStatus> {
+        let (git_dir, cache) = {
+            let watchers = self.watchers.lock().unwrap();
+            match watchers.get(context_key) {
+                Some(watch) if paths_match(&watch.repo_path, repo_path) => {
+                    (watch.git_dir.clone(), watch.cache.clone())
+                }
+                _ => return git::get_status(&repo_path.to_path_buf()),
+            }
+        };
+
+        let stamp = repo_stamp(repo_path, &git_dir);
+        if let Some((cached_stamp, status)) = cache.lock().unwrap().as_ref() {
+            if *cached_stamp == stamp {
+                return Ok(status.clone());
+            }
+        }
+
+        let status = git::get_status(&repo_path.to_path_buf())?;
+        *cache.lock().unwrap() = Some((stamp, status.clone()));
+        Ok(status)
+    }
+}
+
+struct Worker {
+    app: AppHandle,
+    context_key: String,
+    repo_path: PathBuf,
+    git_dir: PathBuf,
+    cache: StatusCache,
+    active: Arc<Mutex<bool>>,
+}
+
+impl Worker {
+    fn run(self, rx: Receiver<notify::Result<Event>>) {
+        while let Ok(first) = rx.recv() {
+            let mut events = vec![first];
+            let started = Instant::now();
+
+            loop {
+                let remaining = MAX_DELAY.saturating_sub(started.elapsed());
+                if remaining.is_zero() {
+                    break;
+                }
+                match rx.recv_timeout(DEBOUNCE.min(remaining)) {
+                    Ok(event) => events.push(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            if self.has_relevant_change(&events) {
+                self.refresh();
+            }
+        }
+    }
+
+    fn refresh(&self) {
+        let stamp = repo_stamp(&self.repo_path, &self.git_dir);
+        let Ok(status) = git::get_status(&self.repo_path) else {
+            return;
+        };
+
+        let changed = {
+            let mut cache = self.cache.lock().unwrap();
+            let changed = cache
+                .as_ref()
+                .is_none_or(|(_, previous)| *previous != status);
+            *cache = Some((stamp, status.clone()));
+            changed
+        };
+
+        // Held while emitting, so switching repositories can't slip in after the check
+        let active = self.active.lock().unwrap();
+        if changed && *active {
+            let _ = self.app.emit_to(
+                crate::commands::window_label(&self.context_key),
+                STATUS_CHANGED_EVENT,
+                RepoStatusChanged {
+                    context_key: self.context_key.clone(),
+                    status,
+                },
+            );
+        }
+    }
+
+    /// Skips bursts that only touch git internals (objects, logs, lock files, the app's own
+    /// work directories) or ignored files in the working tree.
+    fn has_relevant_change(&self, events: &[notify::Result<Event>]) -> bool {
+        let mut worktree_paths = Vec::new();
+
+        for event in events {
+            let Ok(event) = event else {
+                // Errors usually mean events were dropped; refresh to be safe
+                return true;
+            };
+            if event.need_rescan() {
+                return true;
+            }
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+
+            for path in &event.paths {
+                if let Ok(relative) = path.strip_prefix(&self.git_dir) {
+                    if is_relevant_git_path(relative) {
+                        return true;
+                    }
+                } else if let Ok(relative) = path.strip_prefix(&self.repo_path) {
+                    if !relative.as_os_str().is_empty() {
+                        worktree_paths.push(relative.to_string_lossy().replace('\\', "/"));
+                    }
+                }
+            }
+        }
+
+        if worktree_paths.is_empty() {
+            return false;
+        }
+        worktree_paths.sort();
+        worktree_paths.dedup();
+
+        match git::get_ignored_paths(&self.repo_path, &worktree_paths) {
+            Ok(ignored) => worktree_paths.iter().any(|path| !ignored.contains(path)),
+            Err(_) => true,
+        }
+    }
+}
+
+fn is_relevant_git_path(relative: &Path) -> bool {
+    let mut components = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy());
+    let Some(first) = components.next() else {
+        return false;
+    };
+    if matches!(first.as_ref(), "objects" | "logs") || first.starts_with("everydaygit-") {
+        return false;
+    }
+
+    !relative
+        .file_name()
+        .map(|name| name.to_string_lossy().ends_with(".lock"))
+        .unwrap_or(false)
+}
+
+fn repo_stamp(repo_path: &Path, git_dir: &Path) -> RepoStamp {
+    let index = std::fs::metadata(git_dir.join("index")).ok();
+    RepoStamp {
+        index_modified: index.as_ref().and_then(|m| m.modified().ok()),
+        index_len: index.as_ref().map(|m| m.len()),
+        head: std::fs::read_to_string(git_dir.join("HEAD")).ok(),
+        head_commit: git::resolve_revision(repo_path, "HEAD"),
+        upstream: git::resolve_revision(repo_path, "@{upstream}"),
+    }
+}
+
+fn paths_match(watched: &Path, requested: &Path) -> bool {
+    watched == requested
+        || requested
+            .canonicalize()
+            .map(|path| path == watched)
+            .unwrap_or(false)
+}
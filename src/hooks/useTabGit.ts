@@ -8,9 +8,11 @@ import type {
   RepoStatus,
   Branch,
   CommitInfo,
+  CommitLogPage,
   BranchComparison,
   MergePreview,
   MergeResult,
+  PullResult,
   Worktree,
 } from '@/types';
 import { isDemoMode } from '@/demo/demoMode';
@@ -106,7 +108,7 @@ export const useTabGit = () => {
     }
 
     try {
-      const commits = await invoke<CommitInfo[]>('get_commit_log', { limit, contextKey });
+      const { commits } = await invoke<CommitLogPage>('get_commit_log', { limit, contextKey });
       updateTabGit(tabId, { commits });
     } catch (error) {
       console.error('Failed to get commits:', error);
@@ -347,11 +349,19 @@ export const useTabGit = () => {
     }
 
     try {
-      const result = await invoke<string>('pull_cmd', { contextKey });
+      const result = await invoke<PullResult>('pull_cmd', { contextKey });
       await refreshStatus();
       await refreshCommits();
-      toast.success('Pull realizado!');
-      return result;
+      if (result.outcome === 'conflicts') {
+        toast.warning('Pull parou em conflitos. Resolva-os para continuar.');
+      } else if (result.outcome === 'diverged') {
+        toast.warning('Branch divergiu do remoto. Escolha merge ou rebase para integrar.');
+      } else if (result.autostash_pending) {
+        toast.warning('Pull realizado, mas suas alterações locais não puderam ser reaplicadas e continuam no stash.');
+      } else {
+        toast.success('Pull realizado!');
+      }
+      return result.summary;
     } catch (error) {
       console.error('Failed to pull:', error);
       handleGitError(error, { operation: 'pull' });
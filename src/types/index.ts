@@ -4,10 +4,26 @@ export interface FileStatus {
   staged: boolean;
 }
 
+export interface BranchCommit {
+  hash: string;
+  subject: string;
+  author: string;
+  date: string;
+}
+
 export interface Branch {
   name: string;
   current: boolean;
   remote: boolean;
+  upstream?: string | null;
+  upstream_gone?: boolean;
+  ahead?: number;
+  behind?: number;
+  is_default?: boolean;
+  default_ahead?: number | null;
+  default_behind?: number | null;
+  merged?: boolean;
+  last_commit?: BranchCommit | null;
 }
 
 export interface Worktree {
@@ -23,6 +39,11 @@ export interface CommitInfo {
   date: string;
 }
 
+export interface CommitLogPage {
+  commits: CommitInfo[];
+  next_cursor: string | null;
+}
+
 export interface RepoStatus {
   files: FileStatus[];
   current_branch: string;
@@ -70,6 +91,22 @@ export interface MergeResult {
   conflicts: string[];
 }
 
+export type PullOutcome =
+  | 'up-to-date'
+  | 'fast-forward'
+  | 'merged'
+  | 'rebased'
+  | 'conflicts'
+  | 'diverged';
+
+export interface PullResult {
+  outcome: PullOutcome;
+  strategy: 'merge' | 'rebase' | 'ff-only';
+  summary: string;
+  operation: { kind: string; conflicts: string[] } | null;
+  autostash_pending: boolean;
+}
+
 export interface BranchComparison {
   ahead: number;
   behind: number;